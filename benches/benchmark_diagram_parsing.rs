@@ -1,9 +1,10 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 use sequencer::model::{Config, Source};
 use sequencer::parsing::document::DocumentParser;
 use sequencer::parsing::interaction::InteractionParser;
 use sequencer::parsing::participant::ParticipantParser;
-use sequencer::theme::Theme;
 
 fn get_text() -> Vec<String> {
     ":theme Default
@@ -24,35 +25,60 @@ fn get_text() -> Vec<String> {
         .collect()
 }
 
+/// 10k messages between 50 participants.
+fn get_large_text() -> Vec<String> {
+    (0..10_000)
+        .map(|i| {
+            format!(
+                "Service{} -> Service{}: Message {}",
+                i % 50,
+                (i * 7) % 50,
+                i
+            )
+        })
+        .collect()
+}
+
 fn measure_parse_participants(c: &mut Criterion) {
     let config = Config {
-        output_path: String::new(),
         input_source: Source::Example,
     };
-    let document = DocumentParser::parse(&get_text(), config);
-    let theme = Theme::default();
+    let document = DocumentParser::parse(&get_text(), config).unwrap();
     c.bench_function("parsing participants", |b| {
-        b.iter(|| ParticipantParser::parse(black_box(&document.lines), black_box(&theme)))
+        b.iter(|| ParticipantParser::parse(black_box(&document.lines)))
     });
 }
 
 fn measure_parse_interactions(c: &mut Criterion) {
     let config = Config {
         input_source: Source::Example,
-        output_path: String::new(),
     };
-    let document = DocumentParser::parse(&get_text(), config);
-    let theme = Theme::default();
-    let participants = ParticipantParser::parse(&document.lines, &theme);
+    let document = DocumentParser::parse(&get_text(), config).unwrap();
+    let participants = ParticipantParser::parse(&document.lines);
 
     c.bench_function("parsing interactions", |b| {
         b.iter(|| InteractionParser::parse(black_box(&document.lines), black_box(&participants)))
     });
 }
 
+fn measure_parse_large_diagram(c: &mut Criterion) {
+    let config = Config {
+        input_source: Source::Example,
+    };
+    let document = DocumentParser::parse(&get_large_text(), config).unwrap();
+
+    c.bench_function("parsing 10k interactions", |b| {
+        b.iter(|| {
+            let participants = ParticipantParser::parse(black_box(&document.lines));
+            InteractionParser::parse(black_box(&document.lines), &participants)
+        })
+    });
+}
+
 criterion_group!(
     benches,
     measure_parse_participants,
     measure_parse_interactions,
+    measure_parse_large_diagram,
 );
 criterion_main!(benches);
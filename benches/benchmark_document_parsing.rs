@@ -1,4 +1,6 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 use sequencer::model::{Config, Source};
 use sequencer::parsing::document::DocumentParser;
 
@@ -25,7 +27,6 @@ fn measure_parse_document(c: &mut Criterion) {
     let input = get_text();
     let config = Config {
         input_source: Source::Example,
-        output_path: String::new(),
     };
     c.bench_function("parsing document", |b| {
         b.iter(|| DocumentParser::parse(black_box(&input), black_box(config.clone())))
@@ -1,4 +1,6 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 use sequencer::diagram::Diagram;
 use sequencer::model::{Config, Source};
 use sequencer::parsing::document::DocumentParser;
@@ -29,13 +31,23 @@ fn measure_diagram_parse(c: &mut Criterion) {
         b.iter(|| {
             let config = Config {
                 input_source: Source::Example,
-                output_path: String::new(),
             };
-            let document = DocumentParser::parse(black_box(&input), config);
-            Diagram::parse(document, black_box(theme.clone()))
+            let document = DocumentParser::parse(black_box(&input), config).unwrap();
+            Diagram::parse(document, black_box(theme.clone())).unwrap()
         })
     });
 }
 
-criterion_group!(benches, measure_diagram_parse);
+fn measure_diagram_layout(c: &mut Criterion) {
+    let config = Config {
+        input_source: Source::Example,
+    };
+    let document = DocumentParser::parse(&get_text(), config).unwrap();
+    let diagram = Diagram::parse(document, Theme::default()).unwrap();
+    c.bench_function("diagram layout", |b| {
+        b.iter(|| black_box(&diagram).layout())
+    });
+}
+
+criterion_group!(benches, measure_diagram_parse, measure_diagram_layout);
 criterion_main!(benches);
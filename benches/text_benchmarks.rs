@@ -1,11 +1,35 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use sequencer::rendering::text::{measure_string, rgb_to_u32};
-use sequencer::theme::Theme;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sequencer::layout::scene::Scene;
+use sequencer::rendering::text::{draw_text, measure_string, rgb_to_u32, GlyphCache};
+use sequencer::rendering::RenderContext;
+use sequencer::theme::{FontRole, Theme};
+
+const MESSAGE: &str = "Server -> Client: Response with a fairly long message body";
+
+fn render_context() -> RenderContext {
+    let theme = Theme::default();
+    let scene = Scene {
+        width: 800,
+        height: 100,
+        background: theme.background,
+        elements: vec![],
+    };
+    RenderContext::new(&scene, theme)
+}
 
 fn measure_measure_string(c: &mut Criterion) {
     let theme = Theme::default();
     c.bench_function("measure_string single char", |b| {
-        b.iter(|| measure_string(black_box(&theme), black_box("A"), black_box(20)))
+        b.iter(|| {
+            measure_string(
+                black_box(&theme),
+                FontRole::Message,
+                black_box("A"),
+                black_box(20),
+            )
+        })
     });
 }
 
@@ -15,6 +39,7 @@ fn measure_measure_string_long(c: &mut Criterion) {
         b.iter(|| {
             measure_string(
                 black_box(&theme),
+                FontRole::Title,
                 black_box("Example Sequence Diagram"),
                 black_box(30),
             )
@@ -22,6 +47,43 @@ fn measure_measure_string_long(c: &mut Criterion) {
     });
 }
 
+fn measure_draw_text_cold_cache(c: &mut Criterion) {
+    let mut context = render_context();
+    let colour = context.theme.message_text;
+    c.bench_function("draw_text cold glyph cache", |b| {
+        b.iter(|| {
+            context.glyph_cache = GlyphCache::default();
+            draw_text(
+                &mut context,
+                FontRole::Message,
+                black_box(MESSAGE),
+                black_box(10),
+                black_box(10),
+                black_box(16),
+                colour,
+            )
+        })
+    });
+}
+
+fn measure_draw_text_warm_cache(c: &mut Criterion) {
+    let mut context = render_context();
+    let colour = context.theme.message_text;
+    c.bench_function("draw_text warm glyph cache", |b| {
+        b.iter(|| {
+            draw_text(
+                &mut context,
+                FontRole::Message,
+                black_box(MESSAGE),
+                black_box(10),
+                black_box(10),
+                black_box(16),
+                colour,
+            )
+        })
+    });
+}
+
 fn measure_rgb_to_u32(c: &mut Criterion) {
     c.bench_function("rgb_to_u32", |b| {
         b.iter(|| rgb_to_u32(black_box(128), black_box(64), black_box(32), black_box(255)))
@@ -32,6 +94,8 @@ criterion_group!(
     benches,
     measure_measure_string,
     measure_measure_string_long,
+    measure_draw_text_cold_cache,
+    measure_draw_text_warm_cache,
     measure_rgb_to_u32,
 );
 criterion_main!(benches);
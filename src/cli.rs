@@ -1,8 +1,12 @@
 use clap::{crate_version, Arg, ArgMatches, Command};
+use sequencer::theme::THEME_NAMES;
 
 pub const INPUT_FILE: &str = "input";
 pub const EXAMPLE: &str = "example";
 pub const OUTPUT_FILE: &str = "output";
+pub const FORMAT: &str = "format";
+pub const THEME: &str = "theme";
+pub const THEME_FILE: &str = "theme-file";
 
 pub(crate) fn parse_args() -> ArgMatches {
     Command::new("Sequencer")
@@ -23,6 +27,28 @@ pub(crate) fn parse_args() -> ArgMatches {
                 .num_args(0)
                 .conflicts_with(INPUT_FILE),
         )
+        .arg(
+            Arg::new(FORMAT)
+                .long("format")
+                .help("output format, defaults to the output file's extension")
+                .num_args(1)
+                .value_parser(["png", "svg"]),
+        )
+        .arg(
+            Arg::new(THEME)
+                .long("theme")
+                .help("built-in theme, overrides any `:theme` in the input")
+                .num_args(1)
+                .ignore_case(true)
+                .value_parser(THEME_NAMES),
+        )
+        .arg(
+            Arg::new(THEME_FILE)
+                .long("theme-file")
+                .help("TOML theme file, overrides any `:theme` in the input")
+                .num_args(1)
+                .conflicts_with(THEME),
+        )
         .arg(
             Arg::new(OUTPUT_FILE)
                 .help("sets an output file")
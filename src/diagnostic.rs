@@ -0,0 +1,130 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+// == Severity ============================================
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// == Diagnostic ==========================================
+/// A problem found in the input, pointing at the offending part of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Zero based, matching `Line::line_number`.
+    pub line_number: usize,
+    /// Zero based character columns within the source line.
+    pub columns: Range<usize>,
+    pub reason: String,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(line_number: usize, columns: Range<usize>, reason: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            line_number,
+            columns,
+            reason: reason.into(),
+            help: None,
+        }
+    }
+
+    pub fn warning(line_number: usize, columns: Range<usize>, reason: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(line_number, columns, reason)
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Formats the diagnostic in the style of rustc, quoting the source line
+    /// and underlining the offending columns, e.g.
+    ///
+    /// ```text
+    /// error: missing target participant
+    ///  --> diagram.seq:3:8
+    ///   |
+    /// 3 | Client ->
+    ///   |        ^^
+    /// ```
+    pub fn display(&self, origin: &str, source_line: &str) -> String {
+        let line_number = (self.line_number + 1).to_string();
+        let gutter = " ".repeat(line_number.len());
+        let source_line = source_line.trim_end();
+        let start = self.columns.start.min(source_line.chars().count());
+        let width = self.columns.len().max(1);
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            match self.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            },
+            self.reason
+        );
+        let _ = writeln!(
+            out,
+            "{}--> {}:{}:{}",
+            gutter,
+            origin,
+            line_number,
+            start + 1
+        );
+        let _ = writeln!(out, "{} |", gutter);
+        let _ = writeln!(out, "{} | {}", line_number, source_line);
+        let _ = writeln!(
+            out,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(start),
+            "^".repeat(width)
+        );
+        if let Some(help) = &self.help {
+            let _ = writeln!(out, "{} = help: {}", gutter, help);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_underlines_columns() {
+        let diagnostic = Diagnostic::error(2, 7..9, "missing target participant");
+        assert_eq!(
+            "error: missing target participant\n \
+             --> diagram.seq:3:8\n  \
+             |\n\
+             3 | Client ->\n  \
+             |        ^^\n",
+            diagnostic.display("diagram.seq", "Client ->")
+        );
+    }
+
+    #[test]
+    fn test_display_warning_with_help() {
+        let diagnostic = Diagnostic::warning(9, 0..4, "unknown metadata key `:foo`")
+            .with_help("expected one of `:title`");
+        let display = diagnostic.display("<stdin>", ":foo bar");
+        assert!(display.starts_with("warning: unknown metadata key `:foo`\n"));
+        assert!(display.contains("  --> <stdin>:10:1\n"));
+        assert!(display.contains("10 | :foo bar\n"));
+        assert!(display.contains("   | ^^^^\n"));
+        assert!(display.ends_with("   = help: expected one of `:title`\n"));
+    }
+}
@@ -1,11 +1,19 @@
 use log::info;
 
+use crate::error::{Error, Result};
 use crate::model::Config;
+use crate::parsing::activation::ActivationParser;
+use crate::parsing::autonumber::AutoNumberParser;
 use crate::parsing::document::Document;
+use crate::parsing::fragment::FragmentParser;
+use crate::parsing::header::HeaderParser;
 use crate::parsing::interaction::InteractionParser;
+use crate::parsing::note::NoteParser;
 use crate::theme::Theme;
 use crate::{
-    model::Header, parsing::participant::ParticipantParser, InteractionSet, ParticipantSet,
+    model::{Activation, Fragment, Header, Note, Participants},
+    parsing::participant::ParticipantParser,
+    InteractionSet,
 };
 
 // == Diagram =============================================
@@ -14,24 +22,104 @@ pub struct Diagram {
     pub theme: Theme,
     pub header: Header,
     pub interactions: InteractionSet,
-    pub participants: ParticipantSet,
+    pub notes: Vec<Note>,
+    /// The outermost combined fragments, in input order.
+    pub fragments: Vec<Fragment>,
+    /// Explicit execution occurrences; when there are none, each participant
+    /// is drawn active from its first interaction to its last.
+    pub activations: Vec<Activation>,
+    pub participants: Participants,
     pub config: Config,
 }
 
 impl Diagram {
-    pub fn parse(document: Document, theme: Theme) -> Diagram {
+    /// Build a diagram from a parsed document, failing when there is nothing
+    /// to draw or an interaction can't be resolved.
+    pub fn parse(document: Document, mut theme: Theme) -> Result<Diagram> {
         info!("Document: {:?}", document);
-        let participants = ParticipantParser::parse(&document.lines, &theme);
+        if let Some(footbox) = document.footbox() {
+            theme.footbox = footbox;
+        }
+        let header = HeaderParser::parse(&document.lines);
+        let participants = ParticipantParser::parse(&document.lines);
+        if participants.is_empty() {
+            return Err(Error::NoParticipants);
+        }
 
         info!("Got participants: {:#?}", participants);
-        let interactions = InteractionParser::parse(&document.lines, &participants);
+        let mut interactions = InteractionParser::parse(&document.lines, &participants)?;
+        interactions
+            .iter_mut()
+            .zip(AutoNumberParser::parse(&document.lines))
+            .for_each(|(interaction, number)| interaction.number = number);
+        let notes = NoteParser::parse(&document.lines, &participants)?;
+        let fragments = FragmentParser::parse(&document.lines, &participants);
+        let activations = ActivationParser::parse(&document.lines, &participants);
 
-        Diagram {
+        Ok(Diagram {
             theme,
-            header: Header {},
+            header,
             interactions,
+            notes,
+            fragments,
+            activations,
             participants,
             config: document.config,
-        }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::document::parse_example;
+
+    fn parse(text: &str) -> Result<Diagram> {
+        Diagram::parse(parse_example(text), Theme::default())
+    }
+
+    #[test]
+    fn test_parse_without_interactions_is_an_error() {
+        assert!(matches!(
+            parse(":title Nothing here\n# just a comment"),
+            Err(Error::NoParticipants)
+        ));
+    }
+
+    #[test]
+    fn test_parse_diagram() {
+        let diagram = parse("A -> B: hello\nB --> A").unwrap();
+        assert_eq!(2, diagram.participants.len());
+        assert_eq!(2, diagram.interactions.len());
+    }
+
+    #[test]
+    fn test_parse_declared_participants() {
+        let diagram = parse(
+            "participant DB\nparticipant \"Order Service\" as OS\nClient -> OS: order\nOS -> DB",
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["DB", "Order Service", "Client"],
+            diagram
+                .participants
+                .iter()
+                .map(|p| p.display_name.as_str())
+                .collect::<Vec<_>>()
+        );
+        let first = &diagram.interactions[0];
+        assert_eq!(
+            diagram.participants.id("Client"),
+            Some(first.from_participant)
+        );
+        assert_eq!(diagram.participants.id("OS"), Some(first.to_participant));
+    }
+
+    #[test]
+    fn test_parse_declarations_without_interactions() {
+        let diagram = parse("participant A\nparticipant B").unwrap();
+        assert_eq!(2, diagram.participants.len());
+        assert!(diagram.interactions.is_empty());
+        diagram.layout();
     }
 }
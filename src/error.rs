@@ -0,0 +1,58 @@
+use std::fmt::{Display, Formatter};
+
+// == Error ===============================================
+/// Errors raised while parsing, laying out or rendering a diagram.
+#[derive(Debug)]
+pub enum Error {
+    /// The input had no content at all.
+    EmptyDocument,
+    /// The document has diagnostics of error severity; holds their count.
+    InvalidDocument(usize),
+    /// The document has no participants, so there is nothing to draw.
+    NoParticipants,
+    /// A note names no participants to attach to; holds its line number.
+    EmptyNote(usize),
+    /// An interaction refers to a participant that was never discovered.
+    UnknownParticipant(String),
+    /// Font data could not be parsed.
+    Font(String),
+    /// A theme file could not be read or is invalid.
+    Theme(String),
+    /// The rendered image could not be encoded.
+    Encoding(String),
+    Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::EmptyDocument => write!(f, "the input is empty"),
+            Error::InvalidDocument(1) => write!(f, "aborting due to 1 previous error"),
+            Error::InvalidDocument(n) => write!(f, "aborting due to {} previous errors", n),
+            Error::NoParticipants => write!(f, "the diagram has no participants to draw"),
+            Error::EmptyNote(line) => write!(f, "the note on line {} has no participants", line),
+            Error::UnknownParticipant(name) => write!(f, "unknown participant `{}`", name),
+            Error::Font(reason) => write!(f, "could not load font: {}", reason),
+            Error::Theme(reason) => write!(f, "could not load theme: {}", reason),
+            Error::Encoding(reason) => write!(f, "could not encode image: {}", reason),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::layout::scene::{Element, Point, Stroke};
+use crate::layout::LayoutContext;
+use crate::model::{Fragment, InteractionType, ParticipantId};
+use crate::rendering::text::measure_string;
+use crate::theme::{FontRole, Theme};
+use crate::InteractionSet;
+
+// == Fragments ===========================================
+/// The line numbers of every fragment's start, `else` and `end` lines, each
+/// of which takes a row of its own.
+pub(super) fn marker_lines(fragments: &[Fragment]) -> Vec<usize> {
+    let mut lines = vec![];
+    for fragment in fragments {
+        lines.push(fragment.line_number);
+        for (i, operand) in fragment.operands.iter().enumerate() {
+            if i > 0 {
+                lines.push(operand.line_number);
+            }
+            lines.extend(marker_lines(&operand.fragments));
+        }
+        lines.push(fragment.end_line_number);
+    }
+    lines
+}
+
+/// The width of the tab holding the fragment's operator, and its height.
+fn tab_size(fragment: &Fragment, theme: &Theme) -> (f32, f32) {
+    let padding = theme.partic_padding;
+    let px = theme.message_font_px;
+    let text_w = measure_string(theme, FontRole::Message, fragment.kind.keyword(), px).w;
+    ((text_w + (3 * padding)) as f32, (px + (2 * padding)) as f32)
+}
+
+fn guard_text(guard: &str) -> String {
+    format!("[{}]", guard)
+}
+
+impl LayoutContext<'_> {
+    /// The x coordinates of the left and right edges of the fragment's frame,
+    /// wide enough to clear the activation bars and self references of the
+    /// participants it covers, to enclose the fragments nested inside it and
+    /// to fit its operator and first guard.
+    pub(super) fn fragment_bounds(
+        &self,
+        fragment: &Fragment,
+        interactions: &InteractionSet,
+    ) -> (f32, f32) {
+        let theme = self.theme;
+        let padding = theme.partic_padding as f32;
+        let (first, last) = fragment.participants.unwrap_or((
+            ParticipantId(0),
+            ParticipantId(self.participant_rects.len().saturating_sub(1)),
+        ));
+        let margin = ((theme.activation_bar_w / 2) + (2 * theme.partic_padding)) as f32;
+        let has_self_ref = interactions.iter().any(|i| {
+            i.interaction_type == InteractionType::SelfRef
+                && i.from_participant == last
+                && (fragment.line_number..fragment.end_line_number).contains(&i.line_number)
+        });
+        let loop_w = match has_self_ref {
+            true => theme.self_ref_w as f32,
+            false => 0.0,
+        };
+        let (mut left, mut right) = (
+            self.center_x(first) - margin,
+            self.center_x(last) + margin + loop_w,
+        );
+
+        for nested in fragment.operands.iter().flat_map(|o| &o.fragments) {
+            let (nested_left, nested_right) = self.fragment_bounds(nested, interactions);
+            left = left.min(nested_left - (2.0 * padding));
+            right = right.max(nested_right + (2.0 * padding));
+        }
+
+        let guard_w = fragment.operands[0].guard.as_deref().map_or(0.0, |guard| {
+            let px = theme.message_font_px;
+            measure_string(theme, FontRole::Message, &guard_text(guard), px).w as f32
+                + (2.0 * padding)
+        });
+        (
+            left,
+            right.max(left + tab_size(fragment, theme).0 + guard_w),
+        )
+    }
+
+    /// Draws the fragment's frame with its operator in a tab at the top left,
+    /// a dashed separator above each further operand and each operand's guard,
+    /// then the fragments nested within it.
+    pub(super) fn layout_fragment(&mut self, fragment: &Fragment, interactions: &InteractionSet) {
+        let theme = self.theme;
+        let padding = theme.partic_padding;
+        let px = theme.message_font_px;
+        let (left, right) = self.fragment_bounds(fragment, interactions);
+        let top = self.marker_y(fragment.line_number);
+        let bottom = self.marker_y(fragment.end_line_number);
+        let stroke = Stroke::solid(theme.fragment_border, 1.0);
+
+        self.elements.push(Element::Box {
+            x: left,
+            y: top,
+            w: right - left,
+            h: bottom - top,
+            fill: None,
+            stroke: Some(stroke.clone()),
+        });
+
+        // the operator in a tab with its bottom right corner cut off
+        let cut = padding as f32;
+        let (tab_w, tab_h) = tab_size(fragment, theme);
+        let tab = vec![
+            Point::new(left, top),
+            Point::new(left + tab_w, top),
+            Point::new(left + tab_w, top + tab_h - cut),
+            Point::new(left + tab_w - cut, top + tab_h),
+            Point::new(left, top + tab_h),
+        ];
+        self.elements.push(Element::Polygon {
+            points: tab.clone(),
+            fill: theme.background,
+        });
+        self.elements.push(Element::Line {
+            points: tab[1..].to_vec(),
+            stroke: stroke.clone(),
+        });
+        self.text(
+            fragment.kind.keyword(),
+            FontRole::Message,
+            left as usize + padding,
+            top as usize + (padding / 2),
+            px,
+        );
+
+        for (i, operand) in fragment.operands.iter().enumerate() {
+            let (guard_x, y) = if i == 0 {
+                (left + tab_w + cut, top)
+            } else {
+                let y = self.marker_y(operand.line_number);
+                self.elements.push(Element::Line {
+                    points: vec![Point::new(left, y), Point::new(right, y)],
+                    stroke: Stroke::dashed(theme.fragment_border, 1.0, vec![6.0, 4.0]),
+                });
+                (left + cut, y)
+            };
+            if let Some(guard) = &operand.guard {
+                self.text(
+                    &guard_text(guard),
+                    FontRole::Message,
+                    guard_x as usize,
+                    y as usize + (padding / 2),
+                    px,
+                );
+            }
+            for nested in &operand.fragments {
+                self.layout_fragment(nested, interactions);
+            }
+        }
+    }
+}
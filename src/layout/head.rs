@@ -0,0 +1,334 @@
+use crate::layout::scene::{Element, Point, Stroke};
+use crate::layout::LayoutContext;
+use crate::model::{Participant, ParticipantKind};
+use crate::rendering::text::measure_string;
+use crate::rendering::Rect;
+use crate::theme::{FontRole, Theme};
+
+// == Head Shapes =========================================
+/// The size of a participant's head around a label of the given size, see
+/// [`label_offset`] for where the label sits within it.
+///
+/// Box-like kinds wrap the label; the UML icons and the actor's stick figure
+/// are drawn above it.
+pub(super) fn head_size(
+    kind: ParticipantKind,
+    label_w: usize,
+    label_h: usize,
+    theme: &Theme,
+) -> (usize, usize) {
+    let cap = cap_size(theme);
+    match kind {
+        ParticipantKind::Participant => (label_w, label_h),
+        ParticipantKind::Database => (label_w, label_h + (4 * cap)),
+        ParticipantKind::Queue => (label_w + (3 * cap), label_h),
+        ParticipantKind::Actor
+        | ParticipantKind::Boundary
+        | ParticipantKind::Control
+        | ParticipantKind::Entity => {
+            let (icon_w, icon_h) = icon_size(kind, theme);
+            (label_w.max(icon_w), icon_h + theme.partic_padding + label_h)
+        }
+    }
+}
+
+/// Where the label's top left corner sits relative to the head's rect.
+fn label_offset(
+    kind: ParticipantKind,
+    rect_w: usize,
+    label_w: usize,
+    theme: &Theme,
+) -> (usize, usize) {
+    let cap = cap_size(theme);
+    match kind {
+        ParticipantKind::Participant => (0, 0),
+        ParticipantKind::Database => (0, (5 * cap) / 2),
+        ParticipantKind::Queue => (cap, 0),
+        ParticipantKind::Actor
+        | ParticipantKind::Boundary
+        | ParticipantKind::Control
+        | ParticipantKind::Entity => (
+            rect_w.saturating_sub(label_w) / 2,
+            icon_size(kind, theme).1 + theme.partic_padding,
+        ),
+    }
+}
+
+/// The radius of a database's caps and a queue's ends.
+fn cap_size(theme: &Theme) -> usize {
+    (theme.partic_font_px / 5).max(theme.partic_padding)
+}
+
+/// The size of the figure drawn above an icon kind's label; it scales with
+/// the participant font so the label never dwarfs it.
+fn icon_size(kind: ParticipantKind, theme: &Theme) -> (usize, usize) {
+    let px = theme.partic_font_px;
+    match kind {
+        ParticipantKind::Actor => (px, px * 3 / 2),
+        ParticipantKind::Boundary => (px * 2, px),
+        _ => (px, px),
+    }
+}
+
+impl LayoutContext<'_> {
+    /// Draws the participant's head shape and label within `rect`, the same
+    /// content rect that [`super::participant_center_x`] is based on.
+    pub(super) fn layout_head(&mut self, participant: &Participant, rect: Rect) {
+        let theme = self.theme;
+        let padding = theme.partic_padding;
+        let fill = Some(theme.participant_fill);
+        let stroke = Stroke::solid(theme.participant_border, 0.5);
+
+        // the outline wrapped around a box-like kind's label
+        let left = (rect.x + padding) as f32;
+        let top = (rect.y + padding) as f32;
+        let w = (rect.w + (padding * 2)) as f32;
+        let h = (rect.h + (padding * 2)) as f32;
+        let cap = cap_size(theme) as f32;
+        let cx = super::participant_center_x(&rect, theme) as f32;
+
+        match participant.kind {
+            ParticipantKind::Participant => self.elements.push(Element::Box {
+                x: left,
+                y: top,
+                w,
+                h,
+                fill,
+                stroke: Some(stroke),
+            }),
+            ParticipantKind::Database => {
+                // the bottom cap first so the body hides its back half
+                let (rx, bottom) = (w / 2.0, top + h - cap);
+                self.elements.push(Element::Ellipse {
+                    cx,
+                    cy: bottom,
+                    rx,
+                    ry: cap,
+                    fill,
+                    stroke: Some(stroke.clone()),
+                });
+                self.elements.push(Element::Box {
+                    x: left,
+                    y: top + cap,
+                    w,
+                    h: bottom - top - cap,
+                    fill,
+                    stroke: None,
+                });
+                for x in [left, left + w] {
+                    self.elements.push(Element::Line {
+                        points: vec![Point::new(x, top + cap), Point::new(x, bottom)],
+                        stroke: stroke.clone(),
+                    });
+                }
+                self.elements.push(Element::Ellipse {
+                    cx,
+                    cy: top + cap,
+                    rx,
+                    ry: cap,
+                    fill,
+                    stroke: Some(stroke),
+                });
+            }
+            ParticipantKind::Queue => {
+                // a tube lying on its side, its open end facing right
+                let (ry, cy, right) = (h / 2.0, top + (h / 2.0), left + w - cap);
+                self.elements.push(Element::Ellipse {
+                    cx: left + cap,
+                    cy,
+                    rx: cap,
+                    ry,
+                    fill,
+                    stroke: Some(stroke.clone()),
+                });
+                self.elements.push(Element::Box {
+                    x: left + cap,
+                    y: top,
+                    w: right - left - cap,
+                    h,
+                    fill,
+                    stroke: None,
+                });
+                for y in [top, top + h] {
+                    self.elements.push(Element::Line {
+                        points: vec![Point::new(left + cap, y), Point::new(right, y)],
+                        stroke: stroke.clone(),
+                    });
+                }
+                self.elements.push(Element::Ellipse {
+                    cx: right,
+                    cy,
+                    rx: cap,
+                    ry,
+                    fill,
+                    stroke: Some(stroke),
+                });
+            }
+            kind => self.layout_icon(kind, cx, top, stroke),
+        }
+
+        let label = &participant.display_name;
+        let label_w = measure_string(theme, FontRole::Participant, label, theme.partic_font_px).w;
+        let (dx, dy) = label_offset(participant.kind, rect.w, label_w, theme);
+        self.text(
+            label,
+            FontRole::Participant,
+            rect.x + (2 * padding) + dx,
+            rect.y + padding + dy,
+            theme.partic_font_px,
+        );
+    }
+
+    /// The stick figure or UML icon of an icon kind, centred on `cx` below
+    /// `top`.
+    fn layout_icon(&mut self, kind: ParticipantKind, cx: f32, top: f32, stroke: Stroke) {
+        let fill = Some(self.theme.participant_fill);
+        let (icon_w, icon_h) = icon_size(kind, self.theme);
+        let (icon_w, icon_h) = (icon_w as f32, icon_h as f32);
+        let stroke = Stroke {
+            width: 1.5,
+            ..stroke
+        };
+
+        if kind == ParticipantKind::Actor {
+            let r = icon_h / 6.0;
+            let (neck, hip, arms) = (top + (2.0 * r), top + (icon_h * 0.6), top + (icon_h * 0.4));
+            let half_w = icon_w / 2.0;
+            self.elements.push(Element::Ellipse {
+                cx,
+                cy: top + r,
+                rx: r,
+                ry: r,
+                fill,
+                stroke: Some(stroke.clone()),
+            });
+            for points in [
+                vec![Point::new(cx, neck), Point::new(cx, hip)],
+                vec![Point::new(cx - half_w, arms), Point::new(cx + half_w, arms)],
+                vec![
+                    Point::new(cx - half_w, top + icon_h),
+                    Point::new(cx, hip),
+                    Point::new(cx + half_w, top + icon_h),
+                ],
+            ] {
+                self.elements.push(Element::Line {
+                    points,
+                    stroke: stroke.clone(),
+                });
+            }
+            return;
+        }
+
+        // the remaining kinds are all built around a circle
+        let r = icon_h / 2.0;
+        let cy = top + r;
+        match kind {
+            ParticipantKind::Boundary => {
+                // a vertical bar to the left, joined to the circle
+                let bar_x = cx - (icon_w / 2.0);
+                self.elements.push(Element::Line {
+                    points: vec![Point::new(bar_x, top), Point::new(bar_x, top + icon_h)],
+                    stroke: stroke.clone(),
+                });
+                self.elements.push(Element::Line {
+                    points: vec![Point::new(bar_x, cy), Point::new(cx - r, cy)],
+                    stroke: stroke.clone(),
+                });
+            }
+            ParticipantKind::Entity => {
+                self.elements.push(Element::Line {
+                    points: vec![
+                        Point::new(cx - r, top + icon_h),
+                        Point::new(cx + r, top + icon_h),
+                    ],
+                    stroke: stroke.clone(),
+                });
+            }
+            _ => {}
+        }
+        self.elements.push(Element::Ellipse {
+            cx,
+            cy,
+            rx: r,
+            ry: r,
+            fill,
+            stroke: Some(stroke.clone()),
+        });
+        if kind == ParticipantKind::Control {
+            // an arrow head on top of the circle, pointing anticlockwise
+            let head = self.theme.arrow_head_size as f32;
+            self.elements.push(Element::Line {
+                points: vec![
+                    Point::new(cx + (head / 2.0), top - (head / 2.0)),
+                    Point::new(cx - (head / 2.0), top),
+                    Point::new(cx + (head / 2.0), top + (head / 2.0)),
+                ],
+                stroke,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::Diagram;
+    use crate::layout::scene::Scene;
+    use crate::parsing::document::parse_example;
+
+    fn layout(text: &str) -> Scene {
+        Diagram::parse(parse_example(text), Theme::default())
+            .unwrap()
+            .layout()
+    }
+
+    #[test]
+    fn test_head_size_fits_label() {
+        let theme = Theme::default();
+        for kind in ParticipantKind::ALL {
+            for label_w in [4, 400] {
+                let (w, h) = head_size(kind, label_w, 20, &theme);
+                let (dx, dy) = label_offset(kind, w, label_w, &theme);
+                assert!(dx + label_w <= w, "{:?}", kind);
+                assert!(dy + 20 <= h, "{:?}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn test_heads_are_bottom_aligned() {
+        let scene = layout("actor User\ndatabase DB\nUser -> DB\nDB -> Server");
+        let lifeline_tops = scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Line { points, stroke } if !stroke.dash.is_empty() => Some(points[0].y),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(3, lifeline_tops.len());
+        assert!(lifeline_tops.iter().all(|y| *y == lifeline_tops[0]));
+    }
+
+    #[test]
+    fn test_actor_label_is_below_figure() {
+        let scene = layout("actor User\nUser -> Server");
+        let head = scene
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                Element::Ellipse { cy, ry, .. } => Some(cy + ry),
+                _ => None,
+            })
+            .unwrap();
+        let label = scene
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                Element::Text(t) if t.content == "User" => Some(t.y),
+                _ => None,
+            })
+            .unwrap();
+        assert!(label > head);
+    }
+}
@@ -0,0 +1,987 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use itertools::Itertools;
+use log::info;
+
+use crate::diagram::Diagram;
+use crate::layout::scene::{Element, Point, Scene, Stroke, TextRun};
+use crate::model::{
+    Activation, Fragment, HeadStyle, Header, Interaction, InteractionType, LineStyle, Note,
+    Participant, ParticipantId, Participants,
+};
+use crate::rendering::text::measure_string;
+use crate::rendering::Rect;
+use crate::theme::{FontRole, Theme};
+use crate::InteractionSet;
+
+mod fragment;
+mod head;
+mod note;
+pub mod scene;
+
+/// Positions every part of a [`Diagram`] so that backends only have to paint.
+struct LayoutContext<'a> {
+    theme: &'a Theme,
+    /// The measured name of each participant, indexed by `ParticipantId`.
+    participant_rects: Vec<Rect>,
+    /// The rows taken by each interaction and note.
+    rows: Rows,
+    /// The y coordinate below which interaction rows start.
+    rows_top: usize,
+    /// The participant created by each interaction, by `Interaction::index`.
+    creating: HashMap<usize, ParticipantId>,
+    elements: Vec<Element>,
+}
+
+impl Diagram {
+    /// Lays the diagram out as a positioned [`Scene`].
+    pub fn layout(&self) -> Scene {
+        let theme = &self.theme;
+        let header_height = header_height(&self.header, theme);
+        let participant_rects = participant_rects(
+            &self.participants,
+            theme,
+            theme.document_border_width + header_height,
+        );
+        let rows_top = participant_rects
+            .iter()
+            .map(|r| participant_bottom(r, theme))
+            .max()
+            .unwrap_or(theme.document_border_width + header_height);
+
+        // created participants' heads sit on the arrow of their first message
+        let creating = self
+            .participants
+            .iter()
+            .filter(|p| p.created)
+            .filter(|p| {
+                self.interactions
+                    .get(p.active_from)
+                    .is_some_and(|i| [i.from_participant, i.to_participant].contains(&p.id))
+            })
+            .map(|p| (p.active_from, p.id))
+            .collect::<HashMap<_, _>>();
+        let head_heights = creating
+            .iter()
+            .map(|(index, id)| (*index, head_height(&participant_rects[id.0], theme)))
+            .collect();
+        let markers = fragment::marker_lines(&self.fragments)
+            .into_iter()
+            .chain(self.participants.iter().filter_map(|p| p.destroyed))
+            .collect_vec();
+
+        let mut context = LayoutContext {
+            theme,
+            participant_rects,
+            rows: Rows::new(
+                &self.interactions,
+                &self.notes,
+                &markers,
+                &head_heights,
+                theme,
+            ),
+            rows_top,
+            creating,
+            elements: vec![],
+        };
+        context.place_created_heads();
+        let rows_bottom = context.row_y(context.rows.total);
+        context.reserve_left_space(&self.notes, &self.fragments, &self.interactions);
+
+        // declared participants may never take part in an interaction, and
+        // explicit activations replace the first-to-last activation bars
+        let used = self
+            .interactions
+            .iter()
+            .flat_map(|i| [i.from_participant, i.to_participant])
+            .filter(|_| self.activations.is_empty())
+            .collect::<HashSet<_>>();
+
+        context.layout_header(&self.header);
+        self.participants
+            .iter()
+            .for_each(|p| context.layout_participant(p, rows_bottom, used.contains(&p.id)));
+        self.activations
+            .iter()
+            .for_each(|a| context.layout_activation(a));
+        self.fragments
+            .iter()
+            .for_each(|f| context.layout_fragment(f, &self.interactions));
+        self.interactions
+            .iter()
+            .sorted_by_key(|k| k.index)
+            .for_each(|i| context.layout_interaction(i));
+        self.notes
+            .iter()
+            .zip(context.rows.notes.clone())
+            .for_each(|(note, rows)| context.layout_note(note, rows));
+
+        let bottom = match theme.footbox {
+            true => self
+                .participants
+                .iter()
+                .map(|p| participant_bottom(&context.footbox_rect(p.id, rows_bottom), theme))
+                .max()
+                .unwrap_or(rows_bottom),
+            false => rows_bottom,
+        };
+
+        let right = context
+            .elements
+            .iter()
+            .map(Element::right)
+            .fold(0.0, f32::max);
+        info!("Laid out {} elements", context.elements.len());
+
+        Scene {
+            width: right.ceil() as usize + theme.document_border_width,
+            height: bottom + theme.document_border_width,
+            background: theme.background,
+            elements: context.elements,
+        }
+    }
+}
+
+// == Rows ================================================
+/// The rows, in units of `interaction_row_h`, taken by each interaction,
+/// note and fragment line as they are stacked in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rows {
+    /// Indexed by `Interaction::index`.
+    pub interactions: Vec<Range<usize>>,
+    /// In the same order as the notes they were built from.
+    pub notes: Vec<Range<usize>>,
+    /// The row of each fragment's start, `else` and `end` line, and of each
+    /// `destroy` line, by line number.
+    pub markers: HashMap<usize, usize>,
+    pub total: usize,
+}
+
+impl Rows {
+    /// Self references take two rows so the loop can come back one row
+    /// lower, notes as many as their text needs and marker lines, such as
+    /// fragment and `destroy` lines, one each.
+    ///
+    /// `created` maps the index of each interaction that creates a
+    /// participant to the height of that participant's head, which is
+    /// centred on the arrow; the interaction gets extra rows above and below
+    /// the arrow to fit it.
+    pub fn new(
+        interactions: &InteractionSet,
+        notes: &[Note],
+        markers: &[usize],
+        created: &HashMap<usize, usize>,
+        theme: &Theme,
+    ) -> Rows {
+        let row_h = theme.interaction_row_h;
+        let padding = theme.partic_padding;
+        let mut steps = interactions
+            .iter()
+            .map(|i| {
+                let height = match i.interaction_type {
+                    InteractionType::SelfRef => 2,
+                    _ => 1,
+                };
+                let (above, below) = match created.get(&(i.index as usize)) {
+                    Some(head_h) => (
+                        ((head_h / 2) + padding).div_ceil(row_h) - 1,
+                        ((head_h / 2) + theme.message_font_px + (2 * padding)).div_ceil(row_h) - 1,
+                    ),
+                    None => (0, 0),
+                };
+                (i.line_number, above, height, below)
+            })
+            .chain(
+                notes
+                    .iter()
+                    .map(|n| (n.line_number, 0, note::note_rows(n, theme), 0)),
+            )
+            .chain(markers.iter().map(|line_number| (*line_number, 0, 1, 0)))
+            .enumerate()
+            .collect_vec();
+        steps.sort_by_key(|(_, (line_number, ..))| *line_number);
+
+        let mut ranges = vec![0..0; steps.len()];
+        let mut total = 0;
+        for (step, (_, above, height, below)) in steps {
+            ranges[step] = total + above..total + above + height;
+            total += above + height + below;
+        }
+        let marker_rows = ranges.split_off(interactions.len() + notes.len());
+        let notes = ranges.split_off(interactions.len());
+        Rows {
+            interactions: ranges,
+            notes,
+            markers: markers
+                .iter()
+                .copied()
+                .zip(marker_rows)
+                .map(|(line_number, rows)| (line_number, rows.start))
+                .collect(),
+            total,
+        }
+    }
+}
+
+/// The header's lines of text paired with their font and size; the title
+/// comes first.
+fn header_lines<'h>(header: &'h Header, theme: &Theme) -> Vec<(&'h str, FontRole, usize)> {
+    [
+        (&header.title, FontRole::Title, theme.title_font_px),
+        (&header.author, FontRole::Message, theme.message_font_px),
+        (&header.date, FontRole::Message, theme.message_font_px),
+    ]
+    .into_iter()
+    .filter_map(|(text, font, px)| {
+        text.as_deref()
+            .filter(|t| !t.is_empty())
+            .map(|t| (t, font, px))
+    })
+    .collect()
+}
+
+/// The vertical space taken by the header, including the gap below it, or
+/// zero when there is no title, author or date.
+fn header_height(header: &Header, theme: &Theme) -> usize {
+    let lines = header_lines(header, theme);
+    if lines.is_empty() {
+        return 0;
+    }
+    lines
+        .iter()
+        .map(|(_, _, px)| px + theme.partic_padding)
+        .sum::<usize>()
+        + theme.partic_h_gap
+}
+
+/// Measures each participant's name and sizes its head shape to fit, placing
+/// them left to right in index order. Labels share the tallest name's height
+/// and heads are aligned along their bottom edges so lifelines start level.
+fn participant_rects(participants: &Participants, theme: &Theme, y: usize) -> Vec<Rect> {
+    let labels = participants
+        .iter()
+        .map(|p| {
+            measure_string(
+                theme,
+                FontRole::Participant,
+                &p.display_name,
+                theme.partic_font_px,
+            )
+        })
+        .collect_vec();
+    let label_h = labels.iter().map(|r| r.h).max().unwrap_or(0);
+
+    let mut current_x = theme.document_border_width;
+    let mut rects = participants
+        .iter()
+        .zip(labels)
+        .map(|(p, label)| {
+            let (w, h) = head::head_size(p.kind, label.w, label_h, theme);
+            let rect = Rect {
+                x: current_x,
+                y,
+                w,
+                h,
+            };
+            current_x += theme.partic_h_gap + w;
+            rect
+        })
+        .collect_vec();
+
+    let max_height = rects.iter().map(|r| r.h).max().unwrap_or(0);
+    rects.iter_mut().for_each(|r| {
+        r.y += max_height - r.h;
+    });
+    rects
+}
+
+/// The x coordinate of the centre of the participant's header box.
+fn participant_center_x(rect: &Rect, theme: &Theme) -> usize {
+    rect.x + (2 * theme.partic_padding) + (rect.w / 2)
+}
+
+/// The y coordinate of the bottom edge of the participant's header box.
+fn participant_bottom(rect: &Rect, theme: &Theme) -> usize {
+    rect.y + rect.h + (3 * theme.partic_padding)
+}
+
+/// The height of the participant's header box.
+fn head_height(rect: &Rect, theme: &Theme) -> usize {
+    participant_bottom(rect, theme) - rect.y
+}
+
+/// Half the width of the participant's header box.
+fn head_half_w(rect: &Rect, theme: &Theme) -> f32 {
+    (participant_center_x(rect, theme) - rect.x) as f32
+}
+
+impl LayoutContext<'_> {
+    /// The y coordinate of the arrow at the given interaction row.
+    fn row_y(&self, row: usize) -> usize {
+        self.rows_top + ((row + 1) * self.theme.interaction_row_h)
+    }
+
+    /// The y coordinate of a marker line such as a fragment's edge or a
+    /// `destroy`, half way between its row and the row above.
+    fn marker_y(&self, line_number: usize) -> f32 {
+        let row = self.rows.markers[&line_number];
+        (self.row_y(row) - (self.theme.interaction_row_h / 2)) as f32
+    }
+
+    /// Moves the head of each created participant down to centre it on the
+    /// arrow of the interaction that creates it.
+    fn place_created_heads(&mut self) {
+        let theme = self.theme;
+        for (index, id) in self.creating.clone() {
+            let y = self.row_y(self.rows.interactions[index].start);
+            let rect = &mut self.participant_rects[id.0];
+            rect.y = y - (head_height(rect, theme) / 2);
+        }
+    }
+
+    /// Moves every participant right, if needed, so that notes and fragment
+    /// frames sticking out past the left-most lifeline stay inside the
+    /// document border.
+    fn reserve_left_space(
+        &mut self,
+        notes: &[Note],
+        fragments: &[Fragment],
+        interactions: &InteractionSet,
+    ) {
+        let border = self.theme.document_border_width as f32;
+        let left = notes
+            .iter()
+            .map(|n| self.note_left(n, note::note_size(n, self.theme).0))
+            .chain(
+                fragments
+                    .iter()
+                    .map(|f| self.fragment_bounds(f, interactions).0),
+            )
+            .fold(border, f32::min);
+        let shift = (border - left).ceil() as usize;
+        self.participant_rects.iter_mut().for_each(|r| r.x += shift);
+    }
+
+    /// The participant's head repeated at the foot of its lifeline; unlike
+    /// the header, footbox heads are aligned along their top edges.
+    fn footbox_rect(&self, participant: ParticipantId, rows_bottom: usize) -> Rect {
+        Rect {
+            y: rows_bottom.saturating_sub(self.theme.partic_padding),
+            ..self.participant_rects[participant.0]
+        }
+    }
+
+    fn center_x(&self, participant: ParticipantId) -> f32 {
+        participant_center_x(&self.participant_rects[participant.0], self.theme) as f32
+    }
+
+    fn text(&mut self, content: &str, font: FontRole, x: usize, y: usize, px: usize) {
+        let width = measure_string(self.theme, font, content, px).w;
+        let colour = self.theme.text_colour(font);
+        self.elements.push(Element::Text(TextRun {
+            content: content.to_owned(),
+            x: x as f32,
+            y: y as f32,
+            px,
+            font,
+            colour,
+            width: width as f32,
+        }));
+    }
+
+    fn layout_header(&mut self, header: &Header) {
+        let x = self.theme.document_border_width;
+        let mut y = self.theme.document_border_width;
+        for (text, font, px) in header_lines(header, self.theme) {
+            self.text(text, font, x, y, px);
+            y += px + self.theme.partic_padding;
+        }
+    }
+
+    fn layout_participant(&mut self, participant: &Participant, rows_bottom: usize, used: bool) {
+        let theme = self.theme;
+        let rect = self.participant_rects[participant.id.0];
+        let x = self.center_x(participant.id);
+
+        // dashed lifeline from the bottom of the header box through every row,
+        // or to the X of a destroyed participant
+        let end = participant
+            .destroyed
+            .map_or(rows_bottom as f32, |line_number| self.marker_y(line_number));
+        self.elements.push(Element::Line {
+            points: vec![
+                Point::new(x, participant_bottom(&rect, theme) as f32),
+                Point::new(x, end),
+            ],
+            stroke: Stroke::dashed(theme.lifeline, 0.5, vec![4.0, 4.0]),
+        });
+
+        if theme.activation_bars && used {
+            let half_row = (theme.interaction_row_h / 2) as f32;
+            let first_row = self.rows.interactions[participant.active_from].start;
+            let last_row = self.rows.interactions[participant.active_to].end - 1;
+            let top = self.row_y(first_row) as f32 - half_row;
+            let bottom = self.row_y(last_row) as f32 + half_row;
+            self.activation_bar(participant.id, x, top, bottom);
+        }
+
+        self.layout_head(participant, rect);
+        if participant.destroyed.is_some() {
+            let arm = theme.activation_bar_w as f32;
+            let stroke = Stroke::solid(theme.arrow, 1.5);
+            for dx in [-arm, arm] {
+                self.elements.push(Element::Line {
+                    points: vec![Point::new(x - dx, end - arm), Point::new(x + dx, end + arm)],
+                    stroke: stroke.clone(),
+                });
+            }
+        } else if theme.footbox {
+            let rect = self.footbox_rect(participant.id, rows_bottom);
+            self.layout_head(participant, rect);
+        }
+    }
+
+    /// An explicit activation runs from the arrow that starts it, or from
+    /// where a self reference comes back, to the arrow that ends it, offset
+    /// to the right by half a bar for each level of re-entrance.
+    fn layout_activation(&mut self, activation: &Activation) {
+        let theme = self.theme;
+        if !theme.activation_bars {
+            return;
+        }
+        let bar_w = theme.activation_bar_w as f32;
+        let x = self.center_x(activation.participant) + (activation.depth as f32 * bar_w / 2.0);
+        let top = self.row_y(self.rows.interactions[activation.from].end - 1) as f32;
+        let bottom = self.row_y(self.rows.interactions[activation.to].start) as f32;
+        let min_h = (theme.interaction_row_h / 2) as f32;
+        self.activation_bar(activation.participant, x, top, bottom.max(top + min_h));
+    }
+
+    /// A bar centred on `x` between `top` and `bottom`, clipped to start
+    /// below the participant's head, which may be lower down when created.
+    fn activation_bar(&mut self, participant: ParticipantId, x: f32, top: f32, bottom: f32) {
+        let theme = self.theme;
+        let bar_w = theme.activation_bar_w as f32;
+        let head_bottom = participant_bottom(&self.participant_rects[participant.0], theme);
+        let top = top.max(head_bottom as f32);
+        if bottom <= top {
+            return;
+        }
+        self.elements.push(Element::Box {
+            x: x - (bar_w / 2.0),
+            y: top,
+            w: bar_w,
+            h: bottom - top,
+            fill: Some(theme.activation_fill),
+            stroke: Some(Stroke::solid(theme.lifeline, 1.0)),
+        });
+    }
+
+    fn layout_interaction(&mut self, interaction: &Interaction) {
+        let theme = self.theme;
+        let row = self.rows.interactions[interaction.index as usize].start;
+        let from_x = self.center_x(interaction.from_participant);
+        let y = self.row_y(row) as f32;
+        let shaft = match interaction.arrow_style.line {
+            LineStyle::Solid => Stroke::solid(theme.arrow, 1.0),
+            LineStyle::Dashed => Stroke::dashed(theme.arrow, 1.0, vec![6.0, 4.0]),
+        };
+        let label = interaction.label();
+        let message = label.as_deref();
+        let px = theme.message_font_px;
+
+        match interaction.interaction_type {
+            InteractionType::SelfRef => {
+                // a loop out to the right of the lifeline that comes back one
+                // row lower, with the message beside it
+                let loop_w = theme.self_ref_w as f32;
+                let bottom = self.row_y(row + 1) as f32;
+                self.elements.push(Element::Line {
+                    points: vec![
+                        Point::new(from_x, y),
+                        Point::new(from_x + loop_w, y),
+                        Point::new(from_x + loop_w, bottom),
+                        Point::new(from_x, bottom),
+                    ],
+                    stroke: shaft,
+                });
+                self.arrow_head(from_x, bottom, -1.0, interaction.arrow_style.head);
+
+                if let Some(message) = message {
+                    let text_x = (from_x + loop_w) as usize + theme.partic_padding;
+                    let text_y = ((y + bottom) as usize / 2).saturating_sub(px / 2);
+                    self.text(message, FontRole::Message, text_x, text_y, px);
+                }
+            }
+            InteractionType::L2R | InteractionType::R2L => {
+                let mut to_x = self.center_x(interaction.to_participant);
+                // a message creating a participant points at its head
+                let to = interaction.to_participant;
+                if self.creating.get(&(interaction.index as usize)) == Some(&to) {
+                    let half_w = head_half_w(&self.participant_rects[to.0], theme);
+                    to_x -= half_w.copysign(to_x - from_x);
+                }
+                self.elements.push(Element::Line {
+                    points: vec![Point::new(from_x, y), Point::new(to_x, y)],
+                    stroke: shaft,
+                });
+
+                // arrow head points from the sender towards the receiver
+                let direction = if to_x >= from_x { 1.0 } else { -1.0 };
+                self.arrow_head(to_x, y, direction, interaction.arrow_style.head);
+
+                if let Some(message) = message {
+                    let text_rect = measure_string(theme, FontRole::Message, message, px);
+                    let mid_x = (from_x + to_x) as usize / 2;
+                    let text_x = mid_x.saturating_sub(text_rect.w / 2);
+                    let text_y = (y as usize).saturating_sub(px + theme.partic_padding);
+                    self.text(message, FontRole::Message, text_x, text_y, px);
+                }
+            }
+        }
+    }
+
+    /// An arrow head with its tip at `(tip_x, y)`, pointing right when
+    /// `direction` is positive and left when negative.
+    fn arrow_head(&mut self, tip_x: f32, y: f32, direction: f32, head_style: HeadStyle) {
+        let head = self.theme.arrow_head_size as f32;
+        let back_x = tip_x - (direction * head);
+        let points = vec![
+            Point::new(back_x, y - (head / 2.0)),
+            Point::new(tip_x, y),
+            Point::new(back_x, y + (head / 2.0)),
+        ];
+        self.elements.push(match head_style {
+            HeadStyle::Filled => Element::Polygon {
+                points,
+                fill: self.theme.arrow,
+            },
+            HeadStyle::Open => Element::Line {
+                points,
+                stroke: Stroke::solid(self.theme.arrow, 1.0),
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::NotePlacement;
+    use crate::parsing::document::parse_example;
+
+    fn layout(text: &str) -> Scene {
+        layout_with(text, Theme::default())
+    }
+
+    fn layout_with(text: &str, theme: Theme) -> Scene {
+        Diagram::parse(parse_example(text), theme).unwrap().layout()
+    }
+
+    /// The top and bottom of each activation bar, keyed by its centre x.
+    fn bars(scene: &Scene, theme: &Theme) -> Vec<(f32, f32, f32)> {
+        let bar_w = theme.activation_bar_w as f32;
+        scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Box { x, y, w, h, .. } if *w == bar_w => {
+                    Some((x + (bar_w / 2.0), *y, y + h))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The x, top and bottom of each lifeline.
+    fn lifelines(scene: &Scene) -> Vec<(f32, f32, f32)> {
+        scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Line { points, stroke }
+                    if !stroke.dash.is_empty() && points[0].x == points[1].x =>
+                {
+                    Some((points[0].x, points[0].y, points[1].y))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn texts(scene: &Scene) -> Vec<&TextRun> {
+        scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Text(t) => Some(t),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn make_interaction(index: u32, interaction_type: InteractionType) -> Interaction {
+        Interaction {
+            index,
+            line_number: index as usize,
+            from_participant: ParticipantId(0),
+            to_participant: ParticipantId(0),
+            interaction_type,
+            arrow_style: Default::default(),
+            message: None,
+            number: None,
+        }
+    }
+
+    #[test]
+    fn test_self_ref_takes_two_rows() {
+        let interactions = vec![
+            make_interaction(0, InteractionType::L2R),
+            make_interaction(1, InteractionType::SelfRef),
+            make_interaction(2, InteractionType::R2L),
+        ];
+        let rows = Rows::new(&interactions, &[], &[], &HashMap::new(), &Theme::default());
+        assert_eq!(vec![0..1, 1..3, 3..4], rows.interactions);
+        assert_eq!(4, rows.total);
+    }
+
+    #[test]
+    fn test_notes_take_rows_in_input_order() {
+        // notes on lines 0 and 2, interactions on lines 1 and 3
+        let interactions = (0..2)
+            .map(|index| Interaction {
+                line_number: (index as usize * 2) + 1,
+                ..make_interaction(index, InteractionType::L2R)
+            })
+            .collect_vec();
+        let note = |line_number: usize, lines: usize| Note {
+            line_number,
+            placement: NotePlacement::Over,
+            participants: (ParticipantId(0), ParticipantId(0)),
+            text: vec!["text".to_string(); lines],
+        };
+        let theme = Theme::default();
+        let notes = vec![note(0, 1), note(2, 4)];
+        let rows = Rows::new(&interactions, &notes, &[], &HashMap::new(), &theme);
+        assert_eq!(1, rows.notes[0].len());
+        assert_eq!(1..2, rows.interactions[0]);
+        assert_eq!(2, rows.notes[1].start);
+        assert!(rows.notes[1].len() > 1);
+        assert_eq!(rows.notes[1].end, rows.interactions[1].start);
+        assert_eq!(rows.interactions[1].end, rows.total);
+    }
+
+    #[test]
+    fn test_participant_rects_share_max_height() {
+        let scene = layout("A -> LongName");
+        let names = texts(&scene);
+        assert_eq!("A", names[0].content);
+        assert_eq!("LongName", names[1].content);
+        assert_eq!(names[0].y, names[1].y);
+        assert!(names[1].x > names[0].x + names[0].width);
+    }
+
+    #[test]
+    fn test_messages_are_in_row_order_below_participants() {
+        let scene = layout("Client -> Server: first\nServer -> Client: second");
+        let texts = texts(&scene);
+        let client = texts.iter().find(|t| t.content == "Client").unwrap();
+        let first = texts.iter().find(|t| t.content == "first").unwrap();
+        let second = texts.iter().find(|t| t.content == "second").unwrap();
+        assert!(first.y > client.y);
+        assert_eq!(
+            Theme::default().interaction_row_h as f32,
+            second.y - first.y
+        );
+    }
+
+    #[test]
+    fn test_header_pushes_participants_down() {
+        let plain = layout("A -> B");
+        let titled = layout(":title Title\nA -> B");
+        let y = |scene: &Scene| texts(scene).iter().find(|t| t.content == "A").unwrap().y;
+        assert!(y(&titled) > y(&plain));
+        assert!(titled.height > plain.height);
+    }
+
+    #[test]
+    fn test_self_ref_widens_scene() {
+        let plain = layout("A -> B");
+        let looped = layout("A -> B\nB -> B: a long self referencing message");
+        assert!(looped.width > plain.width);
+        let message = texts(&looped)
+            .into_iter()
+            .find(|t| t.content.starts_with("a long"))
+            .unwrap();
+        assert!(looped.width as f32 >= message.x + message.width);
+    }
+
+    #[test]
+    fn test_self_ref_message_is_beside_loop() {
+        let scene = layout("A -> A: Parses");
+        let line = scene
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                Element::Line { points, .. } if points.len() == 4 => Some(points),
+                _ => None,
+            })
+            .unwrap();
+        let message = texts(&scene)
+            .into_iter()
+            .find(|t| t.content == "Parses")
+            .unwrap();
+        assert!(message.x > line[1].x);
+        assert!(message.y > line[0].y && message.y < line[3].y);
+    }
+
+    #[test]
+    fn test_declared_participant_draws_display_name() {
+        let scene = layout("participant \"Order Service\" as OS\nClient -> OS: order");
+        let names = texts(&scene);
+        assert_eq!("Order Service", names[0].content);
+        assert_eq!("Client", names[1].content);
+        assert!(names.iter().all(|t| t.content != "OS"));
+    }
+
+    #[test]
+    fn test_footbox_repeats_heads_below_lifelines() {
+        let plain = layout("A -> B");
+        let footed = layout(":footbox on\nA -> B");
+        assert!(footed.height > plain.height);
+
+        let names = texts(&footed)
+            .into_iter()
+            .filter(|t| t.content == "A")
+            .collect_vec();
+        assert_eq!(2, names.len());
+        assert!(names[1].y > names[0].y);
+        assert!(footed.height as f32 > names[1].y + names[1].px as f32);
+
+        let lifeline_bottom = footed
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                Element::Line { points, stroke } if !stroke.dash.is_empty() => Some(points[1].y),
+                _ => None,
+            })
+            .unwrap();
+        assert!(names[1].y >= lifeline_bottom);
+    }
+
+    #[test]
+    fn test_footbox_metadata_overrides_theme() {
+        let document = parse_example(":footbox off\nA -> B");
+        let theme = Theme {
+            footbox: true,
+            ..Theme::default()
+        };
+        let scene = Diagram::parse(document, theme).unwrap().layout();
+        assert_eq!(1, texts(&scene).iter().filter(|t| t.content == "A").count());
+    }
+
+    #[test]
+    fn test_note_sits_between_messages() {
+        let scene = layout("A -> B: first\nnote over A, B: one\\ntwo\nB -> A: second");
+        let texts = texts(&scene);
+        let y = |content: &str| texts.iter().find(|t| t.content == content).unwrap().y;
+        assert!(y("one") > y("first"));
+        assert!(y("two") > y("one"));
+        assert!(y("second") > y("two"));
+    }
+
+    #[test]
+    fn test_notes_reserve_space_left_of_first_lifeline() {
+        let plain = layout("A -> B");
+        let noted = layout("note left of A: a long note on the left\nA -> B");
+        let x = |scene: &Scene| texts(scene).iter().find(|t| t.content == "A").unwrap().x;
+        assert!(x(&noted) > x(&plain));
+
+        let note = texts(&noted)
+            .into_iter()
+            .find(|t| t.content.starts_with("a long"))
+            .unwrap();
+        assert!(note.x >= Theme::default().document_border_width as f32);
+        assert!(note.x + note.width < x(&noted) + texts(&noted)[0].width);
+    }
+
+    #[test]
+    fn test_note_right_of_last_lifeline_widens_scene() {
+        let plain = layout("A -> B");
+        let noted = layout("A -> B\nnote right of B: a long note on the right");
+        assert!(noted.width > plain.width);
+    }
+
+    #[test]
+    fn test_fragment_frame_encloses_its_messages() {
+        let scene =
+            layout("A -> B: before\nalt ok\nB -> C: yes\nelse\nB -> A: no\nend\nA -> B: after");
+        let texts = texts(&scene);
+        let y = |content: &str| texts.iter().find(|t| t.content == content).unwrap().y;
+        let (top, bottom) = scene
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                // the frame is the only box left unfilled
+                Element::Box {
+                    y, h, fill: None, ..
+                } => Some((*y, y + h)),
+                _ => None,
+            })
+            .unwrap();
+        assert!(y("before") < top);
+        assert!(top < y("[ok]") && y("[ok]") < y("yes"));
+        assert!(y("no") < bottom && bottom < y("after"));
+
+        let separator = scene
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                Element::Line { points, stroke }
+                    if !stroke.dash.is_empty() && points[0].y == points[1].y =>
+                {
+                    Some(points[0].y)
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert!(y("yes") < separator && separator < y("no"));
+    }
+
+    #[test]
+    fn test_fragments_reserve_space_left_of_first_lifeline() {
+        let plain = layout("A -> B");
+        let framed = layout("loop\nopt\nA -> B\nend\nend");
+        let x = |scene: &Scene| texts(scene).iter().find(|t| t.content == "A").unwrap().x;
+        assert!(x(&framed) > x(&plain));
+    }
+
+    #[test]
+    fn test_reentrant_activation_is_offset() {
+        let theme = Theme::default();
+        let scene = layout("A -> +B: call\nB -> +B: again\nB --> -B\nB --> -A\nA -> C");
+        let bars = scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Box { x, y, w, h, .. } if *w == theme.activation_bar_w as f32 => {
+                    Some((*x, *y, y + h))
+                }
+                _ => None,
+            })
+            .collect_vec();
+        // only the explicit activations are drawn
+        assert_eq!(2, bars.len());
+        let (outer, inner) = (bars[0], bars[1]);
+        assert_eq!(inner.0 - outer.0, (theme.activation_bar_w / 2) as f32);
+        assert!(outer.1 < inner.1 && inner.2 < outer.2);
+    }
+
+    #[test]
+    fn test_created_participant_head_sits_on_its_first_message() {
+        let theme = Theme::default();
+        let scene = layout("A -> B: first\ncreate W\nB -> W: new\nW --> B: done\ndestroy W");
+        let texts = texts(&scene);
+        let text = |content: &str| *texts.iter().find(|t| t.content == content).unwrap();
+        let head = text("W");
+        assert!(head.y > text("first").y);
+        assert!(head.y < text("done").y);
+        assert!(text("new").x + text("new").width < head.x);
+
+        // the lifeline ends at the X, half a row below the last message
+        let done_y = text("done").y + (theme.message_font_px + theme.partic_padding) as f32;
+        let crossed = scene.elements.iter().any(|e| {
+            matches!(e, Element::Line { points, .. }
+                if points.len() == 2 && points[1].y - points[0].y == 2.0 * theme.activation_bar_w as f32
+                    && points[0].y + theme.activation_bar_w as f32
+                        == done_y + (theme.interaction_row_h / 2) as f32)
+        });
+        assert!(crossed);
+    }
+
+    #[test]
+    fn test_destroyed_participant_has_no_footbox() {
+        let scene = layout(":footbox on\nA -> B\ndestroy B");
+        let count = |name: &str| texts(&scene).iter().filter(|t| t.content == name).count();
+        assert_eq!(2, count("A"));
+        assert_eq!(1, count("B"));
+    }
+
+    #[test]
+    fn test_autonumber_prefixes_message_labels() {
+        let scene = layout(":autonumber 10 5 \"[000]\"\nA -> B: hello\nB -> A\nA -> A: self");
+        let texts = texts(&scene);
+        for label in ["[010] hello", "[015]", "[020] self"] {
+            assert!(texts.iter().any(|t| t.content == label), "{}", label);
+        }
+    }
+
+    #[test]
+    fn test_lifelines_run_from_heads_through_last_row() {
+        let theme = Theme::default();
+        let scene = layout("A -> B: one\nB -> A: two");
+        let head_bottoms = scene
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                Element::Box {
+                    y,
+                    h,
+                    stroke: Some(s),
+                    ..
+                } if s.colour == theme.participant_border => Some(y + h),
+                _ => None,
+            })
+            .collect_vec();
+        let label = texts(&scene)
+            .into_iter()
+            .find(|t| t.content == "two")
+            .unwrap();
+        let last_arrow_y = label.y + (theme.message_font_px + theme.partic_padding) as f32;
+
+        let lifelines = lifelines(&scene);
+        assert_eq!(2, lifelines.len());
+        for ((_, top, bottom), head_bottom) in lifelines.into_iter().zip(head_bottoms) {
+            assert_eq!(head_bottom, top);
+            assert_eq!(last_arrow_y + theme.interaction_row_h as f32, bottom);
+        }
+    }
+
+    #[test]
+    fn test_activation_bars_span_first_to_last_interaction() {
+        let theme = Theme::default();
+        let scene = layout("A -> B: one\nB -> C: two\nA -> B: three");
+        let arrow_y = |content: &str| {
+            let label = texts(&scene)
+                .into_iter()
+                .find(|t| t.content == content)
+                .unwrap();
+            label.y + (theme.message_font_px + theme.partic_padding) as f32
+        };
+        let half_row = (theme.interaction_row_h / 2) as f32;
+        let lifeline_x = lifelines(&scene).iter().map(|l| l.0).collect_vec();
+        let bars = bars(&scene, &theme);
+        assert_eq!(3, bars.len());
+
+        // A and B are active from the first message to the last, C only for
+        // the second
+        let expected = [("one", "three"), ("one", "three"), ("two", "two")];
+        for (x, (from, to)) in lifeline_x.into_iter().zip(expected) {
+            let bar = bars.iter().find(|b| b.0 == x).unwrap();
+            assert_eq!(arrow_y(from) - half_row, bar.1);
+            assert_eq!(arrow_y(to) + half_row, bar.2);
+        }
+    }
+
+    #[test]
+    fn test_no_activation_bars_when_disabled() {
+        let theme = Theme {
+            activation_bars: false,
+            ..Theme::default()
+        };
+        let scene = layout_with("A -> B\nB -> +C\nC --> -B", theme);
+        assert!(bars(&scene, &Theme::default()).is_empty());
+        assert_eq!(3, lifelines(&scene).len());
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use crate::layout::scene::{Element, Point, Stroke};
+use crate::layout::LayoutContext;
+use crate::model::{Note, NotePlacement};
+use crate::rendering::text::measure_string;
+use crate::theme::{FontRole, Theme};
+
+// == Notes ===============================================
+/// The size of a note's box, wrapping its lines of text and leaving room for
+/// the folded corner.
+pub(super) fn note_size(note: &Note, theme: &Theme) -> (usize, usize) {
+    let padding = theme.partic_padding;
+    let text_w = note
+        .text
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| measure_string(theme, FontRole::Note, line, theme.note_font_px).w)
+        .max()
+        .unwrap_or(0);
+    (
+        text_w + (2 * padding) + fold_size(theme),
+        padding + (note.text.len() * line_height(theme)),
+    )
+}
+
+/// The number of interaction rows a note takes up, leaving a gap above and
+/// below it.
+pub(super) fn note_rows(note: &Note, theme: &Theme) -> usize {
+    let height = note_size(note, theme).1 + (2 * theme.partic_padding);
+    height.div_ceil(theme.interaction_row_h).max(1)
+}
+
+fn line_height(theme: &Theme) -> usize {
+    theme.note_font_px + theme.partic_padding
+}
+
+fn fold_size(theme: &Theme) -> usize {
+    theme.partic_padding * 2
+}
+
+impl LayoutContext<'_> {
+    /// The x coordinate of the left edge of a note `w` wide; notes beside a
+    /// lifeline clear its activation bar, and notes over lifelines overhang
+    /// them by the same amount.
+    pub(super) fn note_left(&self, note: &Note, w: usize) -> f32 {
+        let theme = self.theme;
+        let clearance = ((theme.activation_bar_w / 2) + theme.partic_padding) as f32;
+        let w = w as f32;
+        let (first, last) = note.participants;
+        match note.placement {
+            NotePlacement::LeftOf => self.center_x(first) - clearance - w,
+            NotePlacement::RightOf => self.center_x(first) + clearance,
+            NotePlacement::Over => {
+                let left = self.center_x(first) - clearance;
+                let right = self.center_x(last) + clearance;
+                let mid = (left + right) / 2.0;
+                (mid - (w / 2.0)).min(left)
+            }
+        }
+    }
+
+    /// Draws a folded-corner box with the note's text, centred between the
+    /// arrow above its rows and the message label below them.
+    pub(super) fn layout_note(&mut self, note: &Note, rows: Range<usize>) {
+        let theme = self.theme;
+        let padding = theme.partic_padding;
+        let (mut w, h) = note_size(note, theme);
+        if note.placement == NotePlacement::Over {
+            // spanning notes stretch to cover the lifelines they name
+            let (first, last) = note.participants;
+            let clearance = 2.0 * ((theme.activation_bar_w / 2) + padding) as f32;
+            let span = (self.center_x(last) - self.center_x(first) + clearance) as usize;
+            w = w.max(span);
+        }
+        let x = self.note_left(note, w);
+        let top = self.row_y(rows.start) - theme.interaction_row_h;
+        let bottom = self.row_y(rows.end) - theme.message_font_px - padding;
+        let y = (top + (bottom.saturating_sub(top + h) / 2)) as f32;
+        let (w, h, fold) = (w as f32, h as f32, fold_size(theme) as f32);
+
+        let outline = vec![
+            Point::new(x, y),
+            Point::new(x + w - fold, y),
+            Point::new(x + w, y + fold),
+            Point::new(x + w, y + h),
+            Point::new(x, y + h),
+        ];
+        let stroke = Stroke::solid(theme.note_border, 1.0);
+        self.elements.push(Element::Polygon {
+            points: outline.clone(),
+            fill: theme.note_fill,
+        });
+        self.elements.push(Element::Line {
+            points: [outline.as_slice(), &outline[..1]].concat(),
+            stroke: stroke.clone(),
+        });
+        self.elements.push(Element::Line {
+            points: vec![
+                Point::new(x + w - fold, y),
+                Point::new(x + w - fold, y + fold),
+                Point::new(x + w, y + fold),
+            ],
+            stroke,
+        });
+
+        let mut text_y = y as usize + (padding / 2);
+        for line in &note.text {
+            if !line.is_empty() {
+                self.text(
+                    line,
+                    FontRole::Note,
+                    x as usize + padding,
+                    text_y,
+                    theme.note_font_px,
+                );
+            }
+            text_y += line_height(theme);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::theme::{Colour, FontRole};
+
+// == Point ===============================================
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Point { x, y }
+    }
+}
+
+// == Stroke ==============================================
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub colour: Colour,
+    pub width: f32,
+    /// Alternating dash and gap lengths; empty for a solid line.
+    pub dash: Vec<f32>,
+}
+
+impl Stroke {
+    pub fn solid(colour: Colour, width: f32) -> Self {
+        Stroke {
+            colour,
+            width,
+            dash: vec![],
+        }
+    }
+
+    pub fn dashed(colour: Colour, width: f32, dash: Vec<f32>) -> Self {
+        Stroke {
+            colour,
+            width,
+            dash,
+        }
+    }
+}
+
+// == Text Run ============================================
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    pub content: String,
+    /// Left edge of the text.
+    pub x: f32,
+    /// Top of the line box, as used by the fontdue layout.
+    pub y: f32,
+    pub px: usize,
+    pub font: FontRole,
+    pub colour: Colour,
+    /// Measured width, used when sizing the scene.
+    pub width: f32,
+}
+
+// == Element =============================================
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    /// An axis aligned box such as a participant header or activation bar.
+    Box {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        fill: Option<Colour>,
+        stroke: Option<Stroke>,
+    },
+    /// Connected line segments such as a lifeline, message shaft or open
+    /// arrow head.
+    Line {
+        points: Vec<Point>,
+        stroke: Stroke,
+    },
+    /// A closed filled shape such as a sync arrow head.
+    Polygon {
+        points: Vec<Point>,
+        fill: Colour,
+    },
+    /// An axis aligned ellipse such as an actor's head or a database's cap.
+    Ellipse {
+        cx: f32,
+        cy: f32,
+        rx: f32,
+        ry: f32,
+        fill: Option<Colour>,
+        stroke: Option<Stroke>,
+    },
+    Text(TextRun),
+}
+
+impl Element {
+    /// The right-most x coordinate covered by the element.
+    pub fn right(&self) -> f32 {
+        match self {
+            Element::Box { x, w, .. } => x + w,
+            Element::Line { points, .. } | Element::Polygon { points, .. } => {
+                points.iter().map(|p| p.x).fold(0.0, f32::max)
+            }
+            Element::Ellipse { cx, rx, .. } => cx + rx,
+            Element::Text(text) => text.x + text.width,
+        }
+    }
+}
+
+// == Scene ===============================================
+/// A fully positioned diagram, ready for a backend to paint in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub width: usize,
+    pub height: usize,
+    pub background: Colour,
+    pub elements: Vec<Element>,
+}
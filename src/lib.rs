@@ -1,12 +1,13 @@
-use std::collections::HashSet;
-
-use model::{Interaction, Participant};
+pub use error::{Error, Result};
+use model::Interaction;
 
+pub mod diagnostic;
 pub mod diagram;
+pub mod error;
+pub mod layout;
 pub mod model;
 pub mod parsing;
 pub mod rendering;
 pub mod theme;
 
 type InteractionSet = Vec<Interaction>;
-type ParticipantSet = HashSet<Participant>;
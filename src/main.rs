@@ -1,85 +1,126 @@
+use std::fs::File;
 use std::io;
-use std::io::Read;
+use std::io::{BufWriter, Read, Write};
 use std::time::Instant;
 
 use clap::ArgMatches;
 use itertools::Itertools;
-use log::{info, warn};
+use log::info;
 
 use sequencer::diagram::Diagram;
-use sequencer::model::{Config, LineContents, Source};
+use sequencer::model::{Config, OutputFormat, Source};
 use sequencer::parsing::document::DocumentParser;
-use sequencer::theme::Theme;
+use sequencer::theme::{Theme, THEME_FILE_PREFIX};
 
 mod cli;
 
-fn read_from_stdin() -> Vec<String> {
-    let stdin = io::stdin();
-    let mut stdin = stdin.lock();
-    let mut lines = Vec::new();
-
-    let mut line = String::new();
-    while let Ok(n_bytes) = stdin.read_to_string(&mut line) {
-        if n_bytes == 0 {
-            break;
-        }
-        lines.push(line.clone());
-        line.clear();
-    }
-
-    lines
+fn read_from_stdin() -> sequencer::Result<Vec<String>> {
+    let mut data = String::new();
+    io::stdin().lock().read_to_string(&mut data)?;
+    Ok(data.lines().map(|p| p.to_string()).collect_vec())
 }
 
 fn main() {
     pretty_env_logger::init();
     let instant = Instant::now();
 
-    let config = parse_cli_args();
-    println!("Config: {:?}", config);
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+
+    info!(
+        "Finished in {} micros ({}ms)",
+        instant.elapsed().as_micros(),
+        instant.elapsed().as_millis()
+    );
+}
+
+/// Where and how the rendered diagram is written.
+#[derive(Debug)]
+struct Output {
+    path: String,
+    format: OutputFormat,
+}
+
+/// Options that only concern the command line tool.
+#[derive(Debug)]
+struct CliOptions {
+    config: Config,
+    output: Output,
+    /// Built-in theme or `file:` path taking precedence over the document's
+    /// `:theme`.
+    theme: Option<String>,
+}
+
+fn run() -> sequencer::Result<()> {
+    let CliOptions {
+        config,
+        output,
+        theme,
+    } = parse_cli_args();
+    println!("Config: {:?}, Output: {:?}", config, output);
 
     // load in data from file/stdin/etc
-    let data = load_data(&config.input_source);
+    let data = load_data(&config.input_source)?;
     println!("{:?}", data);
 
-    let document = DocumentParser::parse(&data, config);
+    let origin = source_name(&config.input_source);
+    let document = DocumentParser::parse(&data, config)?;
     info!("Document: {:#?}", document);
 
-    document
-        .lines
-        .iter()
-        .filter(|p| p.line_contents == LineContents::Invalid)
-        .for_each(|p| {
-            warn!("Warning: Line {} is bad: {}", p.line_number, p.line_data);
-        });
+    for diagnostic in &document.diagnostics {
+        let source_line = data.get(diagnostic.line_number).map_or("", String::as_str);
+        eprintln!("{}", diagnostic.display(&origin, source_line));
+    }
+    if !document.is_valid {
+        let errors = document.diagnostics.iter().filter(|d| d.is_error()).count();
+        return Err(sequencer::Error::InvalidDocument(errors));
+    }
 
-    let theme = Theme::default();
-    let diagram = Diagram::parse(document, theme);
+    let theme = match theme.as_deref().or(document.theme_name()) {
+        Some(theme) => Theme::resolve(theme)?,
+        None => Theme::default(),
+    };
+    let diagram = Diagram::parse(document, theme)?;
     info!("Diagram: {:#?}", diagram);
 
-    diagram.render();
-
-    info!(
-        "Finished in {} micros ({}ms)",
-        instant.elapsed().as_micros(),
-        instant.elapsed().as_millis()
-    );
+    let mut file = BufWriter::new(File::create(&output.path)?);
+    diagram.render_to(output.format, &mut file)?;
+    file.flush()?;
+    info!("Wrote {}...", output.path);
+    Ok(())
 }
 
-fn parse_cli_args() -> Config {
+fn parse_cli_args() -> CliOptions {
     let cli_options = cli::parse_args();
     let input_source = resolve_input_source(&cli_options);
 
-    let output_path = cli_options
+    let path = cli_options
         .get_one::<String>(cli::OUTPUT_FILE)
         .cloned()
         .unwrap_or_default();
-    Config {
-        input_source,
-        output_path,
+    let format = resolve_output_format(&cli_options, &path);
+    CliOptions {
+        config: Config { input_source },
+        output: Output { path, format },
+        theme: cli_options
+            .get_one::<String>(cli::THEME_FILE)
+            .map(|path| format!("{}{}", THEME_FILE_PREFIX, path))
+            .or_else(|| cli_options.get_one::<String>(cli::THEME).cloned()),
+    }
+}
+
+fn resolve_output_format(options: &ArgMatches, output_path: &str) -> OutputFormat {
+    match options.get_one::<String>(cli::FORMAT).map(String::as_str) {
+        Some("svg") => OutputFormat::Svg,
+        Some(_) => OutputFormat::Png,
+        None if output_path.to_lowercase().ends_with(".svg") => OutputFormat::Svg,
+        None => OutputFormat::Png,
     }
 }
 
-fn load_data(src: &Source) -> Vec<String> {
+fn load_data(src: &Source) -> sequencer::Result<Vec<String>> {
     match src {
         Source::StdIn => {
             println!("Reading from stdin");
@@ -87,19 +128,27 @@ fn load_data(src: &Source) -> Vec<String> {
         }
         Source::File(file_name) => {
             println!("Reading from file {}", file_name);
-            std::fs::read_to_string(file_name)
-                .unwrap()
+            Ok(std::fs::read_to_string(file_name)?
                 .lines()
                 .map(|p| p.to_string())
-                .collect_vec()
+                .collect_vec())
         }
         Source::Example => {
             println!("Using example file");
-            get_text()
+            Ok(get_text())
         }
     }
 }
 
+/// How the input is named in diagnostics.
+fn source_name(src: &Source) -> String {
+    match src {
+        Source::StdIn => "<stdin>".to_string(),
+        Source::File(file_name) => file_name.clone(),
+        Source::Example => "<example>".to_string(),
+    }
+}
+
 fn resolve_input_source(options: &ArgMatches) -> Source {
     if *options.get_one::<bool>(cli::EXAMPLE).unwrap_or(&false) {
         Source::Example
@@ -1,4 +1,7 @@
-use crate::rendering::Rect;
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use itertools::Itertools;
 
 // == Message =============================================
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Clone)]
@@ -19,8 +22,26 @@ pub enum LineContents {
     Empty,
     Comment,
     MetaData(MetaDataType),
-    Interaction(FromParticipant, ToParticipant),
-    InteractionWithMessage(FromParticipant, ToParticipant, InteractionMessage),
+    Participant(ParticipantDeclaration),
+    Note(NoteLine),
+    /// `alt`, `loop` and friends, with an optional guard or label.
+    FragmentStart(FragmentKind, Option<String>),
+    /// `else`, starting another operand of the enclosing fragment.
+    FragmentElse(Option<String>),
+    FragmentEnd,
+    /// `activate X` or `deactivate X`.
+    Activation(ActivationChange, String),
+    /// `create X`, starting the participant's lifeline at its first message.
+    Create(String),
+    /// `destroy X`, ending the participant's lifeline with an X.
+    Destroy(String),
+    Interaction(FromParticipant, ToParticipant, ArrowStyle),
+    InteractionWithMessage(
+        FromParticipant,
+        ToParticipant,
+        ArrowStyle,
+        InteractionMessage,
+    ),
 }
 
 #[derive(Debug, PartialOrd, PartialEq)]
@@ -29,10 +50,69 @@ pub enum MetaDataType {
     FontSize(f32),
     Title(String),
     Author(String),
-    Date,
+    Date(Option<String>),
+    Footbox(bool),
+    AutoNumber(AutoNumber),
     Invalid,
 }
 
+// == Auto Number =========================================
+/// An `:autonumber` line.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum AutoNumber {
+    /// Numbers the messages that follow, counting from the next one.
+    Start(NumberFormat),
+    Stop,
+    Resume,
+}
+
+/// How `:autonumber` counts and formats message numbers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct NumberFormat {
+    pub start: usize,
+    pub step: usize,
+    /// A label such as `[000]`, where the first run of `0`s is replaced by
+    /// the number zero padded to its length; without `0`s the number is
+    /// appended.
+    pub pattern: Option<String>,
+    /// Whether fragments take a number and the messages inside them are
+    /// numbered below it, as in `2.1`.
+    pub hierarchical: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            start: 1,
+            step: 1,
+            pattern: None,
+            hierarchical: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Formats the `counts`-th message at each level of nesting, outermost
+    /// first, each counting from 1.
+    pub fn format(&self, counts: &[usize]) -> String {
+        let (prefix, width, suffix) = match &self.pattern {
+            Some(pattern) => match pattern.find('0') {
+                Some(at) => {
+                    let width = pattern[at..].chars().take_while(|c| *c == '0').count();
+                    (&pattern[..at], width, &pattern[at + width..])
+                }
+                None => (pattern.as_str(), 0, ""),
+            },
+            None => ("", 0, ""),
+        };
+        let number = counts
+            .iter()
+            .map(|count| format!("{:0width$}", self.start + (self.step * (count - 1))))
+            .join(".");
+        format!("{}{}{}", prefix, number, suffix)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FromParticipant(pub String);
 
@@ -42,22 +122,209 @@ pub struct ToParticipant(pub String);
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct InteractionMessage(pub String);
 
+/// A `participant "Order Service" as OS` line, or one starting with another
+/// kind's keyword such as `actor User`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ParticipantDeclaration {
+    pub kind: ParticipantKind,
+    pub display_name: String,
+    pub alias: Option<String>,
+}
+
+impl ParticipantDeclaration {
+    /// The name messages refer to the participant by.
+    pub fn name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.display_name)
+    }
+}
+
+/// A `note left of A: text`, `note right of A: text` or
+/// `note over A, B: text` line.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NoteLine {
+    pub placement: NotePlacement,
+    /// One participant name, or two for a note spanning lifelines.
+    pub participants: Vec<String>,
+    /// The note's lines of text, split at `\n`.
+    pub text: Vec<String>,
+}
+
+// == Arrow Style =======================================
+/// Whether the arrow shaft is drawn solid (`->`) or dashed (`-->`).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+}
+
+/// Whether the arrow head is filled (sync, `->`) or open (async, `->>`).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HeadStyle {
+    #[default]
+    Filled,
+    Open,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArrowStyle {
+    pub line: LineStyle,
+    pub head: HeadStyle,
+    /// A `+` after the arrow activates the receiver, and a `-` deactivates
+    /// the sender.
+    pub activation: Option<ActivationChange>,
+}
+
 // == Header ==============================================
-#[derive(Debug)]
-pub struct Header {}
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+}
+
+// == Participant Kind ====================================
+/// Picks the shape drawn for a participant's head.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParticipantKind {
+    #[default]
+    Participant,
+    Actor,
+    Database,
+    Queue,
+    Boundary,
+    Control,
+    Entity,
+}
+
+impl ParticipantKind {
+    pub const ALL: [ParticipantKind; 7] = [
+        ParticipantKind::Participant,
+        ParticipantKind::Actor,
+        ParticipantKind::Database,
+        ParticipantKind::Queue,
+        ParticipantKind::Boundary,
+        ParticipantKind::Control,
+        ParticipantKind::Entity,
+    ];
+
+    /// The keyword that starts a declaration of this kind.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ParticipantKind::Participant => "participant",
+            ParticipantKind::Actor => "actor",
+            ParticipantKind::Database => "database",
+            ParticipantKind::Queue => "queue",
+            ParticipantKind::Boundary => "boundary",
+            ParticipantKind::Control => "control",
+            ParticipantKind::Entity => "entity",
+        }
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<ParticipantKind> {
+        Self::ALL.into_iter().find(|k| k.keyword() == keyword)
+    }
+}
 
 // == Participant =========================================
-#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+/// Identifies a participant within its [`Participants`] arena; ids are handed
+/// out in column order, left to right.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct ParticipantId(pub usize);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Participant {
+    pub id: ParticipantId,
     pub name: String,
-    pub index: usize,
+    /// The label drawn in the header box; the same as `name` unless declared
+    /// with an alias.
+    pub display_name: String,
+    pub kind: ParticipantKind,
     pub active_from: usize,
     pub active_to: usize,
-    // pub x: usize,
-    // pub y: usize,
-    // pub w: usize,
-    // pub h: usize,
-    pub rect: Rect,
+    /// Whether the header box is drawn at the first message rather than at
+    /// the top.
+    pub created: bool,
+    /// The line number of the `destroy` line that ends the lifeline, if any.
+    pub destroyed: Option<usize>,
+}
+
+// == Participants ========================================
+/// An ordered arena of participants with constant time lookup by id or name.
+#[derive(Debug, Clone, Default)]
+pub struct Participants {
+    participants: Vec<Participant>,
+    ids: HashMap<String, ParticipantId>,
+}
+
+impl Participants {
+    /// The id of the named participant, adding it as the right-most column
+    /// active from `interaction` if it is new.
+    pub fn insert(&mut self, name: &str, interaction: usize) -> ParticipantId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = ParticipantId(self.participants.len());
+        self.participants.push(Participant {
+            id,
+            name: name.to_owned(),
+            display_name: name.to_owned(),
+            kind: ParticipantKind::default(),
+            active_from: interaction,
+            active_to: interaction,
+            created: false,
+            destroyed: None,
+        });
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
+
+    /// Adds a declared participant as the right-most column, or returns the
+    /// existing id if the name is already taken. Messages may use either the
+    /// alias or, when it is not ambiguous, the display name.
+    pub fn declare(&mut self, declaration: &ParticipantDeclaration) -> ParticipantId {
+        if let Some(id) = self.id(declaration.name()) {
+            return id;
+        }
+        let id = self.insert(declaration.name(), 0);
+        self.participants[id.0].display_name = declaration.display_name.clone();
+        self.participants[id.0].kind = declaration.kind;
+        self.ids
+            .entry(declaration.display_name.clone())
+            .or_insert(id);
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<ParticipantId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Participants in column order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Participant> {
+        self.participants.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.participants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.participants.is_empty()
+    }
+}
+
+impl Index<ParticipantId> for Participants {
+    type Output = Participant;
+
+    fn index(&self, id: ParticipantId) -> &Participant {
+        &self.participants[id.0]
+    }
+}
+
+impl IndexMut<ParticipantId> for Participants {
+    fn index_mut(&mut self, id: ParticipantId) -> &mut Participant {
+        &mut self.participants[id.0]
+    }
 }
 
 // == Interaction Type ====================================
@@ -72,16 +339,164 @@ pub enum InteractionType {
 #[derive(Debug)]
 pub struct Interaction {
     pub index: u32,
-    pub from_participant: Participant,
-    pub to_participant: Participant,
+    /// Where the interaction appears in the input, ordering it among notes.
+    pub line_number: usize,
+    pub from_participant: ParticipantId,
+    pub to_participant: ParticipantId,
     pub interaction_type: InteractionType,
+    pub arrow_style: ArrowStyle,
     pub message: Option<Message>,
+    /// The `:autonumber` sequence number, if the message is numbered.
+    pub number: Option<String>,
+}
+
+impl Interaction {
+    /// The text drawn beside the arrow: the message, after its number if it
+    /// has one.
+    pub fn label(&self) -> Option<String> {
+        let message = self
+            .message
+            .as_ref()
+            .map(|m| m.0.as_str())
+            .filter(|m| !m.is_empty());
+        match (&self.number, message) {
+            (Some(number), Some(message)) => Some(format!("{} {}", number, message)),
+            (Some(number), None) => Some(number.clone()),
+            (None, message) => message.map(str::to_owned),
+        }
+    }
+}
+
+// == Fragment ============================================
+/// The operator of a combined fragment, shown in the frame's label tab.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FragmentKind {
+    Alt,
+    Opt,
+    Loop,
+    Par,
+    Critical,
+    Break,
+}
+
+impl FragmentKind {
+    pub const ALL: [FragmentKind; 6] = [
+        FragmentKind::Alt,
+        FragmentKind::Opt,
+        FragmentKind::Loop,
+        FragmentKind::Par,
+        FragmentKind::Critical,
+        FragmentKind::Break,
+    ];
+
+    /// The keyword that starts a fragment of this kind.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            FragmentKind::Alt => "alt",
+            FragmentKind::Opt => "opt",
+            FragmentKind::Loop => "loop",
+            FragmentKind::Par => "par",
+            FragmentKind::Critical => "critical",
+            FragmentKind::Break => "break",
+        }
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<FragmentKind> {
+        Self::ALL.into_iter().find(|k| k.keyword() == keyword)
+    }
+
+    /// Whether `else` may split the fragment into several operands.
+    pub fn allows_else(self) -> bool {
+        matches!(self, FragmentKind::Alt | FragmentKind::Par)
+    }
+}
+
+/// A combined fragment framing part of the sequence.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Fragment {
+    pub kind: FragmentKind,
+    /// The `alt`, `loop`, ... line.
+    pub line_number: usize,
+    /// The `end` line, or the number of lines in the input when the fragment
+    /// is never closed.
+    pub end_line_number: usize,
+    /// At least one; the first starts on the fragment's own line.
+    pub operands: Vec<Operand>,
+    /// The left and right-most participants of the interactions and notes
+    /// inside, or `None` when there are none.
+    pub participants: Option<(ParticipantId, ParticipantId)>,
+}
+
+/// One part of a fragment, such as a branch of an `alt`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Operand {
+    pub line_number: usize,
+    pub guard: Option<String>,
+    /// Fragments nested within this operand.
+    pub fragments: Vec<Fragment>,
+}
+
+// == Activation ==========================================
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActivationChange {
+    Activate,
+    Deactivate,
+}
+
+impl ActivationChange {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            ActivationChange::Activate => "activate",
+            ActivationChange::Deactivate => "deactivate",
+        }
+    }
+}
+
+/// An execution occurrence, drawn as an activation bar on the participant's
+/// lifeline between two interactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activation {
+    pub participant: ParticipantId,
+    /// The `Interaction::index` of the interaction that starts it.
+    pub from: usize,
+    /// The `Interaction::index` of the interaction that ends it, or of the
+    /// last interaction when it is never deactivated.
+    pub to: usize,
+    /// How many of the participant's activations are already open when this
+    /// one starts; re-entrant activations are drawn offset to the right.
+    pub depth: usize,
+}
+
+// == Note ================================================
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
+/// A note in the interaction row sequence.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Note {
+    /// Where the note appears in the input, ordering it among interactions.
+    pub line_number: usize,
+    pub placement: NotePlacement,
+    /// The participant the note is attached to, or the left and right-most
+    /// of those it spans.
+    pub participants: (ParticipantId, ParticipantId),
+    pub text: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub input_source: Source,
-    pub output_path: String,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Svg,
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use log::info;
+
+use crate::model::{Activation, ActivationChange, Line, LineContents, ParticipantId, Participants};
+
+// == Activation Parser ===================================
+#[derive(Debug, Default)]
+pub struct ActivationParser;
+
+impl ActivationParser {
+    /// Pair up each participant's activations and deactivations into
+    /// [`Activation`]s, sorted so that re-entrant ones come after those they
+    /// are nested in.
+    ///
+    /// `activate X` and `deactivate X` lines take effect at the interaction
+    /// above them, or the first one when there is none; `A -> +B` activates
+    /// `B` and `B --> -A` deactivates `B` at that interaction. Stray
+    /// deactivations are ignored and open activations run to the last
+    /// interaction; both are reported as diagnostics by the `DocumentParser`.
+    pub fn parse(document: &[Line], participants: &Participants) -> Vec<Activation> {
+        let mut activations = vec![];
+        let mut open: HashMap<ParticipantId, Vec<usize>> = HashMap::new();
+        let mut interaction_index: usize = 0;
+
+        let mut change = |change: ActivationChange, name: &str, at: usize| {
+            let Some(id) = participants.id(name) else {
+                return;
+            };
+            let stack = open.entry(id).or_default();
+            match change {
+                ActivationChange::Activate => stack.push(at),
+                ActivationChange::Deactivate => {
+                    if let Some(from) = stack.pop() {
+                        activations.push(Activation {
+                            participant: id,
+                            from,
+                            to: at,
+                            depth: stack.len(),
+                        });
+                    }
+                }
+            }
+        };
+
+        for line in document {
+            match &line.line_contents {
+                LineContents::Activation(c, name) => {
+                    change(*c, name, interaction_index.saturating_sub(1));
+                }
+                LineContents::Interaction(f, t, a)
+                | LineContents::InteractionWithMessage(f, t, a, _) => {
+                    match a.activation {
+                        Some(ActivationChange::Activate) => {
+                            change(ActivationChange::Activate, &t.0, interaction_index)
+                        }
+                        Some(ActivationChange::Deactivate) => {
+                            change(ActivationChange::Deactivate, &f.0, interaction_index)
+                        }
+                        None => {}
+                    }
+                    interaction_index += 1;
+                }
+                _ => {}
+            }
+        }
+
+        // with nothing to hang them on there is nothing to draw
+        if interaction_index == 0 {
+            return vec![];
+        }
+        let last = interaction_index - 1;
+        for (id, stack) in open {
+            for (depth, from) in stack.into_iter().enumerate() {
+                activations.push(Activation {
+                    participant: id,
+                    from,
+                    to: last,
+                    depth,
+                });
+            }
+        }
+        activations.sort_by_key(|a| (a.depth, a.participant, a.from));
+
+        info!("Activations: {:?}", activations);
+        activations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::document::parse_example;
+    use crate::parsing::participant::ParticipantParser;
+
+    fn parse(text: &str) -> (Participants, Vec<Activation>) {
+        let document = parse_example(text);
+        let participants = ParticipantParser::parse(&document.lines);
+        let activations = ActivationParser::parse(&document.lines, &participants);
+        (participants, activations)
+    }
+
+    #[test]
+    fn test_parse_activate_lines() {
+        let (participants, activations) =
+            parse("A -> B: call\nactivate B\nB -> C\nB --> A: reply\ndeactivate B");
+        assert_eq!(
+            vec![Activation {
+                participant: participants.id("B").unwrap(),
+                from: 0,
+                to: 2,
+                depth: 0,
+            }],
+            activations
+        );
+    }
+
+    #[test]
+    fn test_parse_activation_shorthand() {
+        let (participants, activations) =
+            parse("A -> +B: call\nB -> +B: again\nB --> -B\nB --> -A");
+        let b = participants.id("B").unwrap();
+        assert_eq!(
+            vec![
+                Activation {
+                    participant: b,
+                    from: 0,
+                    to: 3,
+                    depth: 0,
+                },
+                Activation {
+                    participant: b,
+                    from: 1,
+                    to: 2,
+                    depth: 1,
+                },
+            ],
+            activations
+        );
+    }
+
+    #[test]
+    fn test_parse_unbalanced_activations() {
+        let (participants, activations) = parse("deactivate A\nA -> +B\nB -> C\nactivate C");
+        assert_eq!(2, activations.len());
+        assert_eq!(participants.id("B").unwrap(), activations[0].participant);
+        assert_eq!((0, 1), (activations[0].from, activations[0].to));
+        assert_eq!(participants.id("C").unwrap(), activations[1].participant);
+        assert_eq!((1, 1), (activations[1].from, activations[1].to));
+    }
+}
@@ -0,0 +1,140 @@
+use log::info;
+
+use crate::model::{AutoNumber, Line, LineContents, MetaDataType, NumberFormat};
+
+// == Auto Number Parser ==================================
+#[derive(Debug, Default)]
+pub struct AutoNumberParser;
+
+impl AutoNumberParser {
+    /// The `:autonumber` sequence number of each interaction, in
+    /// `Interaction::index` order.
+    ///
+    /// Messages are counted from the `:autonumber` line above them, so a
+    /// message's number follows from its index; messages between `stop` and
+    /// `resume` are counted but not numbered. With `hierarchical`, each
+    /// fragment takes the next number and the messages inside it are counted
+    /// again from the start below it.
+    pub fn parse(document: &[Line]) -> Vec<Option<String>> {
+        let mut numbers = vec![];
+        let mut format: Option<NumberFormat> = None;
+        let mut stopped = false;
+        // how many messages, or fragments, have been counted at each level of
+        // nesting, outermost first
+        let mut counts = vec![0];
+        let mut depth = 0;
+
+        for line in document {
+            match &line.line_contents {
+                LineContents::MetaData(MetaDataType::AutoNumber(directive)) => match directive {
+                    AutoNumber::Start(start) => {
+                        format = Some(start.clone());
+                        stopped = false;
+                        counts = vec![0; depth + 1];
+                    }
+                    AutoNumber::Stop => stopped = true,
+                    AutoNumber::Resume => stopped = false,
+                },
+                LineContents::FragmentStart(..) => {
+                    depth += 1;
+                    if format.as_ref().is_some_and(|f| f.hierarchical) {
+                        Self::count(&mut counts);
+                        counts.push(0);
+                    }
+                }
+                LineContents::FragmentEnd if depth > 0 => {
+                    depth -= 1;
+                    if format.as_ref().is_some_and(|f| f.hierarchical) && counts.len() > 1 {
+                        counts.pop();
+                    }
+                }
+                LineContents::Interaction(..) | LineContents::InteractionWithMessage(..) => {
+                    let number = format.as_ref().map(|format| {
+                        Self::count(&mut counts);
+                        match format.hierarchical {
+                            true => format.format(&counts),
+                            false => format.format(&counts[counts.len() - 1..]),
+                        }
+                    });
+                    numbers.push(number.filter(|_| !stopped));
+                }
+                _ => {}
+            }
+        }
+
+        info!("Message numbers: {:?}", numbers);
+        numbers
+    }
+
+    fn count(counts: &mut [usize]) {
+        if let Some(count) = counts.last_mut() {
+            *count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::document::parse_example;
+
+    fn parse(text: &str) -> Vec<Option<String>> {
+        AutoNumberParser::parse(&parse_example(text).lines)
+    }
+
+    fn numbers(numbers: &[Option<&str>]) -> Vec<Option<String>> {
+        numbers.iter().map(|n| n.map(str::to_owned)).collect()
+    }
+
+    #[test]
+    fn test_number_format() {
+        let format = NumberFormat {
+            start: 10,
+            step: 5,
+            pattern: Some("[000]".to_string()),
+            hierarchical: false,
+        };
+        assert_eq!("[010]", format.format(&[1]));
+        assert_eq!("[020]", format.format(&[3]));
+        assert_eq!("[015.010]", format.format(&[2, 1]));
+        let plain = NumberFormat {
+            pattern: Some("Step ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!("Step 2.1", plain.format(&[2, 1]));
+        assert_eq!("7", NumberFormat::default().format(&[7]));
+    }
+
+    #[test]
+    fn test_autonumber_counts_from_directive() {
+        assert_eq!(
+            numbers(&[None, Some("[010]"), Some("[015]"), Some("[020]")]),
+            parse("A -> B\n:autonumber 10 5 \"[000]\"\nB -> C\nalt\nC -> A\nend\nA -> B")
+        );
+    }
+
+    #[test]
+    fn test_autonumber_stop_and_resume() {
+        assert_eq!(
+            numbers(&[Some("1"), None, Some("3")]),
+            parse(":autonumber\nA -> B\n:autonumber stop\nB -> C\n:autonumber resume\nC -> A")
+        );
+    }
+
+    #[test]
+    fn test_autonumber_hierarchical() {
+        assert_eq!(
+            numbers(&[
+                Some("1"),
+                Some("2.1"),
+                Some("2.2.1"),
+                Some("2.3"),
+                Some("3")
+            ]),
+            parse(concat!(
+                ":autonumber hierarchical\nA -> B\nalt\nB -> C\n",
+                "opt\nC -> A\nend\nelse\nB -> A\nend\nA -> B"
+            ))
+        );
+    }
+}
@@ -1,28 +1,163 @@
+use std::collections::HashSet;
+use std::ops::Range;
 use std::sync::OnceLock;
 
+use crate::diagnostic::Diagnostic;
+use crate::error::{Error, Result};
 use crate::model::{
-    Config, FromParticipant, InteractionMessage, Line, LineContents, MetaDataType, ToParticipant,
+    ActivationChange, ArrowStyle, AutoNumber, Config, FragmentKind, FromParticipant, HeadStyle,
+    InteractionMessage, Line, LineContents, LineStyle, MetaDataType, NoteLine, NotePlacement,
+    NumberFormat, ParticipantDeclaration, ParticipantKind, ToParticipant,
 };
+use crate::theme::{Theme, THEME_FILE_PREFIX, THEME_NAMES};
 use itertools::Itertools;
 use regex::Regex;
 
 static INTERACTION_REGEX: OnceLock<Regex> = OnceLock::new();
+static ARROW_REGEX: OnceLock<Regex> = OnceLock::new();
+static DECLARATION_REGEX: OnceLock<Regex> = OnceLock::new();
+static NOTE_REGEX: OnceLock<Regex> = OnceLock::new();
+static AUTONUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
 
 fn interaction_regex() -> &'static Regex {
-    INTERACTION_REGEX.get_or_init(|| Regex::new(r"^(.+)\s+-+>+\s+([^:]+):?(.*)$").unwrap())
+    INTERACTION_REGEX
+        .get_or_init(|| Regex::new(r"^(.+)\s+(-+)(>+)\s+([+-]?)\s*([^:]+):?(.*)$").unwrap())
 }
 
+/// Anything that looks like an attempt at an arrow, used for diagnostics.
+fn arrow_regex() -> &'static Regex {
+    ARROW_REGEX.get_or_init(|| Regex::new(r"[-=<>]{2,}").unwrap())
+}
+
+/// `participant Name`, `participant Name as N` or `participant "Any Name" as N`,
+/// where `participant` may be any [`ParticipantKind`] keyword.
+fn declaration_regex() -> &'static Regex {
+    DECLARATION_REGEX.get_or_init(|| {
+        Regex::new(r#"^(\w+)\s+(?:"([^"]+)"|([^\s:"]+))(?:\s+as\s+([^\s:"]+))?$"#).unwrap()
+    })
+}
+
+/// `note left of A: text`, `note right of A: text` or `note over A, B: text`.
+fn note_regex() -> &'static Regex {
+    NOTE_REGEX.get_or_init(|| {
+        Regex::new(r"^note\s+(left\s+of|right\s+of|over)\s+([^:,]+?)(?:\s*,\s*([^:,]+?))?\s*:(.*)$")
+            .unwrap()
+    })
+}
+
+/// The value of an `:autonumber` line: `[start] [step] ["format"] [hierarchical]`.
+fn autonumber_regex() -> &'static Regex {
+    AUTONUMBER_REGEX.get_or_init(|| {
+        Regex::new(r#"^(?:(\d+)(?:\s+(\d+))?)?\s*(?:"([^"]*)")?\s*(hierarchical)?$"#).unwrap()
+    })
+}
+
+/// The character columns, in the raw input line, of a byte range of the
+/// trimmed line.
+fn raw_columns(raw: &str, range: Range<usize>) -> Range<usize> {
+    let indent = raw.len() - raw.trim_start().len();
+    let start = raw[..indent + range.start].chars().count();
+    start..start + raw[indent..][range].chars().count()
+}
+
+/// Whether the line starts with the `note` keyword.
+fn is_note(line: &str) -> bool {
+    line.split(char::is_whitespace).next() == Some("note")
+}
+
+/// Whether the line starts with `activate` or `deactivate`.
+fn is_activation(line: &str) -> bool {
+    matches!(
+        line.split(char::is_whitespace).next(),
+        Some("activate" | "deactivate")
+    )
+}
+
+/// Whether the line starts with `create` or `destroy`.
+fn is_creation(line: &str) -> bool {
+    matches!(
+        line.split(char::is_whitespace).next(),
+        Some("create" | "destroy")
+    )
+}
+
+/// Whether the line starts with a participant kind keyword.
+fn is_declaration(line: &str) -> bool {
+    let keyword = line.split(char::is_whitespace).next().unwrap_or_default();
+    ParticipantKind::from_keyword(keyword).is_some()
+}
+
+const METADATA_KEYS: &[&str] = &[
+    ":theme",
+    ":title",
+    ":author",
+    ":date",
+    ":footbox",
+    ":autonumber",
+];
+/// The largest `:autonumber` start and step, keeping message numbers well
+/// clear of overflow.
+const AUTONUMBER_MAX: usize = 1_000_000;
+const AUTONUMBER_HELP: &str =
+    "expected `[start] [step] [\"format\"] [hierarchical]`, `stop` or `resume`";
+const ARROW_HELP: &str = "use `->`, `-->`, `->>` or `-->>`, e.g. `Client -> Server: Request`";
+const NOTE_HELP: &str =
+    "use `note left of A: text`, `note right of A: text` or `note over A, B: text`";
+const ACTIVATION_HELP: &str = "use `activate A` and `deactivate A`, or `A -> +B` and `B --> -A`";
+const CREATION_HELP: &str =
+    "use `create A` before its first message and `destroy A` after its last";
+const DECLARATION_HELP: &str =
+    "e.g. `participant Server`, `actor User` or `database \"Order Store\" as DB`";
+
 #[derive(Debug)]
 pub struct Document {
     pub config: Config,
     pub lines: Vec<Line>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// `false` when any diagnostic is an error.
     pub is_valid: bool,
 }
 
+impl Document {
+    /// Whether the last `:footbox` line turns the footbox on or off, if any.
+    pub fn footbox(&self) -> Option<bool> {
+        self.lines
+            .iter()
+            .rev()
+            .find_map(|line| match &line.line_contents {
+                LineContents::MetaData(MetaDataType::Footbox(footbox)) => Some(*footbox),
+                _ => None,
+            })
+    }
+
+    /// The theme named by the last `:theme` line, if any.
+    pub fn theme_name(&self) -> Option<&str> {
+        self.lines
+            .iter()
+            .rev()
+            .find_map(|line| match &line.line_contents {
+                LineContents::MetaData(MetaDataType::Style(name)) => Some(name.as_str()),
+                _ => None,
+            })
+    }
+}
+
+/// Parses `text` as the lines of an example document, for tests.
+#[cfg(test)]
+pub(crate) fn parse_example(text: &str) -> Document {
+    let config = Config {
+        input_source: crate::model::Source::Example,
+    };
+    let lines = text.lines().map(str::to_owned).collect_vec();
+    DocumentParser::parse(&lines, config).unwrap()
+}
+
 // == Document Parser =====================================
 pub struct DocumentParser;
 impl DocumentParser {
-    pub fn parse(input: &[String], config: Config) -> Document {
+    /// Classify each input line, failing only when there is no content.
+    pub fn parse(input: &[String], config: Config) -> Result<Document> {
+        let mut diagnostics = vec![];
         let lines = input
             .iter()
             .enumerate()
@@ -34,11 +169,20 @@ impl DocumentParser {
                     LineContents::Comment
                 } else if line_data.starts_with(':') {
                     DocumentParser::parse_metadata(&line_data)
+                } else if let Some(fragment) = DocumentParser::parse_fragment(&line_data) {
+                    fragment
+                } else if let Some(note) = DocumentParser::parse_note(&line_data) {
+                    note
+                } else if let Some(lifeline) = DocumentParser::parse_lifeline(&line_data) {
+                    lifeline
+                } else if let Some(declaration) = DocumentParser::parse_declaration(&line_data) {
+                    declaration
                 } else if line.contains("->") {
                     DocumentParser::parse_interaction(&line_data)
                 } else {
                     LineContents::Invalid
                 };
+                diagnostics.extend(DocumentParser::diagnose(line_number, line, &line_contents));
                 Line {
                     line_number,
                     line_data,
@@ -46,14 +190,408 @@ impl DocumentParser {
                 }
             })
             .collect_vec();
+        diagnostics.extend(DocumentParser::diagnose_declarations(&lines, input));
+        diagnostics.extend(DocumentParser::diagnose_fragments(&lines, input));
+        diagnostics.extend(DocumentParser::diagnose_activations(&lines, input));
+        diagnostics.extend(DocumentParser::diagnose_lifelines(&lines, input));
+        diagnostics.extend(DocumentParser::diagnose_autonumber(&lines, input));
+        diagnostics.sort_by_key(|d| d.line_number);
 
-        Document {
+        if lines.iter().all(|l| l.line_contents == LineContents::Empty) {
+            return Err(Error::EmptyDocument);
+        }
+
+        Ok(Document {
             lines,
             config,
-            is_valid: true,
+            is_valid: !diagnostics.iter().any(Diagnostic::is_error),
+            diagnostics,
+        })
+    }
+
+    /// Explain why a line could not be understood.
+    fn diagnose(line_number: usize, raw: &str, contents: &LineContents) -> Option<Diagnostic> {
+        let line = raw.trim();
+        let columns = |range: Range<usize>| raw_columns(raw, range);
+        let key_help = || format!("expected one of {}", METADATA_KEYS.iter().join(", "));
+
+        match contents {
+            LineContents::MetaData(MetaDataType::Style(name))
+                if !Theme::is_built_in(name) && !name.starts_with(THEME_FILE_PREFIX) =>
+            {
+                let value_start = line.len() - name.len();
+                Some(
+                    Diagnostic::warning(
+                        line_number,
+                        columns(value_start..line.len()),
+                        format!("unknown theme `{}`, using the default theme", name),
+                    )
+                    .with_help(format!(
+                        "expected one of {}, or `{}` followed by a theme file path",
+                        THEME_NAMES.join(", "),
+                        THEME_FILE_PREFIX
+                    )),
+                )
+            }
+            LineContents::MetaData(MetaDataType::Invalid) => {
+                let key_end = line.find(char::is_whitespace).unwrap_or(line.len());
+                let key = &line[..key_end];
+                let value_help = match key {
+                    ":footbox" => Some("expected `on` or `off`"),
+                    ":autonumber" => Some(AUTONUMBER_HELP),
+                    _ => None,
+                };
+                if let Some(help) = value_help {
+                    let value_start =
+                        key_end + (line[key_end..].len() - line[key_end..].trim_start().len());
+                    return Some(
+                        Diagnostic::warning(
+                            line_number,
+                            columns(value_start..line.len()),
+                            format!("invalid `{}` value `{}`", key, &line[value_start..]),
+                        )
+                        .with_help(help),
+                    );
+                }
+                Some(
+                    Diagnostic::warning(
+                        line_number,
+                        columns(0..key_end),
+                        format!("unknown metadata key `{}`", &line[..key_end]),
+                    )
+                    .with_help(key_help()),
+                )
+            }
+            LineContents::Invalid if line.starts_with(':') => {
+                let diagnostic = if METADATA_KEYS.contains(&line) {
+                    Diagnostic::warning(
+                        line_number,
+                        columns(0..line.len()),
+                        format!("metadata key `{}` expects a value", line),
+                    )
+                } else {
+                    Diagnostic::warning(
+                        line_number,
+                        columns(0..line.len()),
+                        format!("unknown metadata key `{}`", line),
+                    )
+                    .with_help(key_help())
+                };
+                Some(diagnostic)
+            }
+            LineContents::Invalid if is_note(line) => Some(
+                Diagnostic::error(line_number, columns(0..line.len()), "malformed note")
+                    .with_help(NOTE_HELP),
+            ),
+            LineContents::Invalid if is_activation(line) => Some(
+                Diagnostic::error(line_number, columns(0..line.len()), "malformed activation")
+                    .with_help(ACTIVATION_HELP),
+            ),
+            LineContents::Invalid if is_creation(line) => {
+                let keyword = line.split(char::is_whitespace).next().unwrap_or_default();
+                Some(
+                    Diagnostic::error(
+                        line_number,
+                        columns(0..line.len()),
+                        format!("malformed `{}` line", keyword),
+                    )
+                    .with_help(CREATION_HELP),
+                )
+            }
+            LineContents::Invalid if is_declaration(line) => Some(
+                Diagnostic::error(
+                    line_number,
+                    columns(0..line.len()),
+                    "malformed participant declaration",
+                )
+                .with_help(DECLARATION_HELP),
+            ),
+            LineContents::Invalid => Some(Self::diagnose_interaction(line_number, line, columns)),
+            _ => None,
         }
     }
 
+    /// Declarations fix the column order, so one that repeats a name or comes
+    /// after the participant's first message is probably a mistake.
+    fn diagnose_declarations(lines: &[Line], input: &[String]) -> Vec<Diagnostic> {
+        let mut declared = HashSet::new();
+        let mut used = HashSet::new();
+        let mut diagnostics = vec![];
+        for line in lines {
+            let columns = raw_columns(&input[line.line_number], 0..line.line_data.len());
+            match &line.line_contents {
+                LineContents::Participant(declaration) => {
+                    let name = declaration.name();
+                    if !declared.insert(name) {
+                        diagnostics.push(Diagnostic::warning(
+                            line.line_number,
+                            columns,
+                            format!("participant `{}` is already declared", name),
+                        ));
+                    } else if used.contains(name) {
+                        diagnostics.push(
+                            Diagnostic::warning(
+                                line.line_number,
+                                columns,
+                                format!("participant `{}` is declared after it is used", name),
+                            )
+                            .with_help("move the declaration above the first message using it"),
+                        );
+                    }
+                }
+                LineContents::Interaction(f, t, _)
+                | LineContents::InteractionWithMessage(f, t, _, _) => {
+                    used.insert(f.0.as_str());
+                    used.insert(t.0.as_str());
+                }
+                LineContents::Note(note) => {
+                    used.extend(note.participants.iter().map(String::as_str));
+                }
+                _ => {}
+            }
+        }
+        diagnostics
+    }
+
+    /// `:autonumber stop` and `resume` only make sense once numbering has
+    /// started.
+    fn diagnose_autonumber(lines: &[Line], input: &[String]) -> Vec<Diagnostic> {
+        let mut started = false;
+        let mut diagnostics = vec![];
+        for line in lines {
+            match &line.line_contents {
+                LineContents::MetaData(MetaDataType::AutoNumber(AutoNumber::Start(_))) => {
+                    started = true
+                }
+                LineContents::MetaData(MetaDataType::AutoNumber(_)) if !started => diagnostics
+                    .push(
+                        Diagnostic::warning(
+                            line.line_number,
+                            raw_columns(&input[line.line_number], 0..line.line_data.len()),
+                            format!("`{}` before numbering is started", line.line_data),
+                        )
+                        .with_help("start numbering with `:autonumber` above it"),
+                    ),
+                _ => {}
+            }
+        }
+        diagnostics
+    }
+
+    /// Participants are created before their first message and not used
+    /// after they are destroyed, each at most once.
+    fn diagnose_lifelines(lines: &[Line], input: &[String]) -> Vec<Diagnostic> {
+        let mut used = HashSet::new();
+        let mut created = HashSet::new();
+        let mut destroyed = HashSet::new();
+        let mut diagnostics = vec![];
+        for line in lines {
+            let columns = raw_columns(&input[line.line_number], 0..line.line_data.len());
+            let error = |reason: String| {
+                Diagnostic::error(line.line_number, columns.clone(), reason)
+                    .with_help(CREATION_HELP)
+            };
+            match &line.line_contents {
+                LineContents::Create(name) => {
+                    if used.contains(name.as_str()) {
+                        diagnostics.push(error(format!(
+                            "participant `{}` is created after it is used",
+                            name
+                        )));
+                    } else if !created.insert(name.as_str()) {
+                        diagnostics
+                            .push(error(format!("participant `{}` is already created", name)));
+                    }
+                }
+                LineContents::Destroy(name) if !destroyed.insert(name.as_str()) => {
+                    diagnostics.push(error(format!(
+                        "participant `{}` is already destroyed",
+                        name
+                    )));
+                }
+                LineContents::Interaction(f, t, _)
+                | LineContents::InteractionWithMessage(f, t, _, _) => {
+                    for name in [f.0.as_str(), t.0.as_str()] {
+                        if destroyed.contains(name) {
+                            diagnostics.push(error(format!(
+                                "participant `{}` is used after it is destroyed",
+                                name
+                            )));
+                        }
+                    }
+                    if created.contains(f.0.as_str()) && !used.contains(f.0.as_str()) {
+                        diagnostics.push(
+                            Diagnostic::warning(
+                                line.line_number,
+                                columns.clone(),
+                                format!(
+                                    "participant `{}` sends a message before receiving one",
+                                    f.0
+                                ),
+                            )
+                            .with_help(concat!(
+                                "a created participant's header is drawn at its first message, ",
+                                "which should be sent to it"
+                            )),
+                        );
+                    }
+                    used.extend([f.0.as_str(), t.0.as_str()]);
+                }
+                _ => {}
+            }
+        }
+        diagnostics
+    }
+
+    /// Every deactivation needs the participant to be active, and every
+    /// activation should be deactivated again.
+    fn diagnose_activations(lines: &[Line], input: &[String]) -> Vec<Diagnostic> {
+        let columns = |line: &Line| raw_columns(&input[line.line_number], 0..line.line_data.len());
+        let mut open: Vec<(&str, &Line)> = vec![];
+        let mut diagnostics = vec![];
+        for line in lines {
+            let (change, name) = match &line.line_contents {
+                LineContents::Activation(change, name) => (*change, name.as_str()),
+                LineContents::Interaction(f, t, a)
+                | LineContents::InteractionWithMessage(f, t, a, _) => match a.activation {
+                    Some(ActivationChange::Activate) => (ActivationChange::Activate, t.0.as_str()),
+                    Some(ActivationChange::Deactivate) => {
+                        (ActivationChange::Deactivate, f.0.as_str())
+                    }
+                    None => continue,
+                },
+                _ => continue,
+            };
+            match change {
+                ActivationChange::Activate => open.push((name, line)),
+                ActivationChange::Deactivate => match open.iter().rposition(|(n, _)| *n == name) {
+                    Some(position) => {
+                        open.remove(position);
+                    }
+                    None => diagnostics.push(
+                        Diagnostic::error(
+                            line.line_number,
+                            columns(line),
+                            format!("participant `{}` is deactivated but not active", name),
+                        )
+                        .with_help(ACTIVATION_HELP),
+                    ),
+                },
+            }
+        }
+        diagnostics.extend(open.into_iter().map(|(name, line)| {
+            Diagnostic::warning(
+                line.line_number,
+                columns(line),
+                format!("participant `{}` is never deactivated", name),
+            )
+            .with_help(format!(
+                "its activation runs to the last message; add `deactivate {}` to end it",
+                name
+            ))
+        }));
+        diagnostics
+    }
+
+    /// Every fragment needs a matching `end`, and only `alt` and `par` can be
+    /// split with `else`.
+    fn diagnose_fragments(lines: &[Line], input: &[String]) -> Vec<Diagnostic> {
+        let columns = |line: &Line, range| raw_columns(&input[line.line_number], range);
+        let mut open: Vec<(FragmentKind, &Line)> = vec![];
+        let mut diagnostics = vec![];
+        for line in lines {
+            match &line.line_contents {
+                LineContents::FragmentStart(kind, _) => open.push((*kind, line)),
+                LineContents::FragmentElse(_) => match open.last() {
+                    None => diagnostics.push(
+                        Diagnostic::error(
+                            line.line_number,
+                            columns(line, 0..4),
+                            "`else` outside of a fragment",
+                        )
+                        .with_help("start the fragment with `alt` or `par`"),
+                    ),
+                    Some((kind, _)) if !kind.allows_else() => diagnostics.push(
+                        Diagnostic::error(
+                            line.line_number,
+                            columns(line, 0..4),
+                            format!("`{}` fragments cannot be split with `else`", kind.keyword()),
+                        )
+                        .with_help("only `alt` and `par` fragments have several operands"),
+                    ),
+                    Some(_) => {}
+                },
+                // a matched `end` is popped by the guard
+                LineContents::FragmentEnd if open.pop().is_none() => diagnostics.push(
+                    Diagnostic::error(
+                        line.line_number,
+                        columns(line, 0..line.line_data.len()),
+                        "`end` without a matching fragment",
+                    )
+                    .with_help("remove it, or start a fragment such as `alt` or `loop` above"),
+                ),
+                _ => {}
+            }
+        }
+        diagnostics.extend(open.into_iter().map(|(kind, line)| {
+            Diagnostic::error(
+                line.line_number,
+                columns(line, 0..kind.keyword().len()),
+                format!("`{}` fragment is never closed", kind.keyword()),
+            )
+            .with_help("add an `end` line after its last message")
+        }));
+        diagnostics
+    }
+
+    fn diagnose_interaction(
+        line_number: usize,
+        line: &str,
+        columns: impl Fn(Range<usize>) -> Range<usize>,
+    ) -> Diagnostic {
+        let arrow = match arrow_regex().find(line) {
+            Some(arrow) => arrow,
+            None => {
+                return Diagnostic::error(
+                    line_number,
+                    columns(0..line.len()),
+                    "expected an interaction, metadata or comment",
+                )
+                .with_help(ARROW_HELP)
+            }
+        };
+
+        let before = &line[..arrow.start()];
+        let after = &line[arrow.end()..];
+        // an activation marker such as `+` in `A -> +B` is not the target
+        let target = after.split(':').next().unwrap_or_default();
+        let target = target.trim().trim_start_matches(['+', '-']);
+        // a well formed arrow is one or more `-` followed by one or more `>`
+        let heads = arrow.as_str().trim_start_matches('-');
+        let is_well_formed =
+            heads.len() < arrow.len() && !heads.is_empty() && heads.chars().all(|c| c == '>');
+
+        let (reason, range) = if !is_well_formed {
+            (
+                format!("malformed arrow `{}`", arrow.as_str()),
+                arrow.range(),
+            )
+        } else if before.trim().is_empty() {
+            ("missing source participant".to_owned(), arrow.range())
+        } else if target.is_empty() {
+            ("missing target participant".to_owned(), arrow.range())
+        } else if !before.ends_with(char::is_whitespace) || !after.starts_with(char::is_whitespace)
+        {
+            (
+                format!("arrow `{}` needs a space on both sides", arrow.as_str()),
+                arrow.range(),
+            )
+        } else {
+            ("could not parse interaction".to_owned(), 0..line.len())
+        };
+        Diagnostic::error(line_number, columns(range), reason).with_help(ARROW_HELP)
+    }
+
     #[inline]
     fn parse_interaction(line: &str) -> LineContents {
         let line = line.trim();
@@ -61,17 +599,149 @@ impl DocumentParser {
             None => LineContents::Invalid,
             Some(captures) => {
                 let from_name = FromParticipant(captures[1].trim().to_owned());
-                let to_name = ToParticipant(captures[2].trim().to_owned());
-                if captures.len() >= 3 && !captures[3].is_empty() {
-                    let msg = InteractionMessage(captures[3].trim_start().to_owned());
-                    LineContents::InteractionWithMessage(from_name, to_name, msg)
+                let arrow_style =
+                    DocumentParser::parse_arrow_style(&captures[2], &captures[3], &captures[4]);
+                let to_name = ToParticipant(captures[5].trim().to_owned());
+                // left for `diagnose_interaction` to report
+                if from_name.0.is_empty() || to_name.0.is_empty() {
+                    return LineContents::Invalid;
+                }
+                if !captures[6].is_empty() {
+                    let msg = InteractionMessage(captures[6].trim_start().to_owned());
+                    LineContents::InteractionWithMessage(from_name, to_name, arrow_style, msg)
                 } else {
-                    LineContents::Interaction(from_name, to_name)
+                    LineContents::Interaction(from_name, to_name, arrow_style)
                 }
             }
         }
     }
 
+    /// A fragment keyword followed by an optional guard or label; a keyword
+    /// followed by an arrow is a participant of that name instead.
+    #[inline]
+    fn parse_fragment(line: &str) -> Option<LineContents> {
+        let (keyword, rest) = line
+            .split_once(char::is_whitespace)
+            .map(|(keyword, rest)| (keyword, rest.trim()))
+            .unwrap_or((line, ""));
+        if rest.starts_with('-') {
+            return None;
+        }
+        let label = Some(rest.to_owned()).filter(|r| !r.is_empty());
+        match keyword {
+            "else" => Some(LineContents::FragmentElse(label)),
+            "end" if label.is_none() => Some(LineContents::FragmentEnd),
+            _ => FragmentKind::from_keyword(keyword)
+                .map(|kind| LineContents::FragmentStart(kind, label)),
+        }
+    }
+
+    /// `activate X`, `deactivate X`, `create X` or `destroy X`; a keyword
+    /// followed by an arrow is a participant of that name instead.
+    #[inline]
+    fn parse_lifeline(line: &str) -> Option<LineContents> {
+        let (keyword, name) = line
+            .split_once(char::is_whitespace)
+            .map(|(keyword, name)| (keyword, name.trim().to_owned()))?;
+        if name.starts_with('-') || name.contains("->") || name.contains(':') {
+            return None;
+        }
+        match keyword {
+            "activate" => Some(LineContents::Activation(ActivationChange::Activate, name)),
+            "deactivate" => Some(LineContents::Activation(ActivationChange::Deactivate, name)),
+            "create" => Some(LineContents::Create(name)),
+            "destroy" => Some(LineContents::Destroy(name)),
+            _ => None,
+        }
+    }
+
+    /// Only `note over` may span two participants; `\n` in the text starts a
+    /// new line.
+    #[inline]
+    fn parse_note(line: &str) -> Option<LineContents> {
+        let captures = note_regex().captures(line)?;
+        let placement = match captures[1].split_whitespace().next()? {
+            "left" => NotePlacement::LeftOf,
+            "right" => NotePlacement::RightOf,
+            _ => NotePlacement::Over,
+        };
+        let participants = [captures.get(2), captures.get(3)]
+            .into_iter()
+            .flatten()
+            .map(|name| name.as_str().trim().to_owned())
+            .collect_vec();
+        if participants.len() > 1 && placement != NotePlacement::Over {
+            return None;
+        }
+        Some(LineContents::Note(NoteLine {
+            placement,
+            participants,
+            text: captures[4]
+                .split("\\n")
+                .map(|line| line.trim().to_owned())
+                .collect(),
+        }))
+    }
+
+    #[inline]
+    fn parse_declaration(line: &str) -> Option<LineContents> {
+        let captures = declaration_regex().captures(line)?;
+        let kind = ParticipantKind::from_keyword(&captures[1])?;
+        let display_name = captures.get(2).or(captures.get(3))?.as_str().to_owned();
+        Some(LineContents::Participant(ParticipantDeclaration {
+            kind,
+            display_name,
+            alias: captures.get(4).map(|a| a.as_str().to_owned()),
+        }))
+    }
+
+    /// `--` gives a dashed (reply) line and `>>` an open (async) head.
+    #[inline]
+    fn parse_arrow_style(shaft: &str, head: &str, activation: &str) -> ArrowStyle {
+        ArrowStyle {
+            line: if shaft.len() > 1 {
+                LineStyle::Dashed
+            } else {
+                LineStyle::Solid
+            },
+            head: if head.len() > 1 {
+                HeadStyle::Open
+            } else {
+                HeadStyle::Filled
+            },
+            activation: match activation {
+                "+" => Some(ActivationChange::Activate),
+                "-" => Some(ActivationChange::Deactivate),
+                _ => None,
+            },
+        }
+    }
+
+    /// `stop`, `resume` or the optional start, step, format and
+    /// `hierarchical` flag of an `:autonumber` line; the step can't be 0.
+    fn parse_autonumber(value: &str) -> Option<AutoNumber> {
+        match value {
+            "stop" => return Some(AutoNumber::Stop),
+            "resume" => return Some(AutoNumber::Resume),
+            _ => {}
+        }
+        let captures = autonumber_regex().captures(value)?;
+        let number = |group: usize| {
+            captures
+                .get(group)
+                .map(|n| n.as_str().parse().ok().filter(|n| *n <= AUTONUMBER_MAX))
+        };
+        let defaults = NumberFormat::default();
+        Some(AutoNumber::Start(NumberFormat {
+            start: number(1).unwrap_or(Some(defaults.start))?,
+            step: number(2)
+                .unwrap_or(Some(defaults.step))
+                .filter(|step| *step > 0)?,
+            pattern: captures.get(3).map(|p| p.as_str().to_owned()),
+            hierarchical: captures.get(4).is_some(),
+        }))
+    }
+
     #[inline]
     fn parse_metadata(line: &str) -> LineContents {
         if let Some((key, value)) = line.trim().split_once(|c: char| c.is_whitespace()) {
@@ -79,10 +749,24 @@ impl DocumentParser {
                 ":theme" => MetaDataType::Style(value.trim().to_owned()),
                 ":title" => MetaDataType::Title(value.trim().to_owned()),
                 ":author" => MetaDataType::Author(value.trim().to_owned()),
-                ":date" => MetaDataType::Date,
+                ":date" => MetaDataType::Date(Some(value.trim().to_owned())),
+                ":footbox" => match value.trim().to_lowercase().as_str() {
+                    "on" => MetaDataType::Footbox(true),
+                    "off" => MetaDataType::Footbox(false),
+                    _ => MetaDataType::Invalid,
+                },
+                ":autonumber" => DocumentParser::parse_autonumber(value.trim())
+                    .map_or(MetaDataType::Invalid, MetaDataType::AutoNumber),
                 _ => MetaDataType::Invalid,
             };
             LineContents::MetaData(meta)
+        } else if line.trim() == ":date" {
+            // a bare `:date` is filled in with today's date
+            LineContents::MetaData(MetaDataType::Date(None))
+        } else if line.trim() == ":autonumber" {
+            LineContents::MetaData(MetaDataType::AutoNumber(AutoNumber::Start(
+                NumberFormat::default(),
+            )))
         } else {
             LineContents::Invalid
         }
@@ -96,12 +780,12 @@ mod tests {
     fn str_to_vec(s: &str) -> Vec<String> {
         s.lines().map(|p| p.to_string()).collect_vec()
     }
+    use crate::diagnostic::Severity;
     use crate::model::Source;
 
     fn make_config() -> Config {
         Config {
             input_source: Source::Example,
-            output_path: String::new(),
         }
     }
 
@@ -140,8 +824,16 @@ mod tests {
     #[test]
     fn test_parse_metadata_date() {
         assert_eq!(
-            LineContents::MetaData(MetaDataType::Date),
-            DocumentParser::parse_metadata(":date today")
+            LineContents::MetaData(MetaDataType::Date(Some("2021-03-04".to_string()))),
+            DocumentParser::parse_metadata(":date 2021-03-04")
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_bare_date() {
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Date(None)),
+            DocumentParser::parse_metadata("  :date  ")
         );
     }
 
@@ -156,7 +848,10 @@ mod tests {
     #[test]
     fn test_parse_metadata_no_value_returns_invalid() {
         // A bare `:title` with no whitespace after → split_once fails → Invalid
-        assert_eq!(LineContents::Invalid, DocumentParser::parse_metadata(":title"));
+        assert_eq!(
+            LineContents::Invalid,
+            DocumentParser::parse_metadata(":title")
+        );
     }
 
     #[test]
@@ -164,7 +859,8 @@ mod tests {
         assert_eq!(
             LineContents::Interaction(
                 FromParticipant("Client".to_string()),
-                ToParticipant("Server".to_string())
+                ToParticipant("Server".to_string()),
+                ArrowStyle::default()
             ),
             DocumentParser::parse_interaction("Client -> Server")
         );
@@ -176,12 +872,39 @@ mod tests {
             LineContents::InteractionWithMessage(
                 FromParticipant("Client".to_string()),
                 ToParticipant("Server".to_string()),
+                ArrowStyle::default(),
                 InteractionMessage("Hello".to_string())
             ),
             DocumentParser::parse_interaction("Client -> Server: Hello")
         );
     }
 
+    #[test]
+    fn test_parse_interaction_arrow_styles() {
+        let cases = [
+            ("A -> B", LineStyle::Solid, HeadStyle::Filled),
+            ("A --> B", LineStyle::Dashed, HeadStyle::Filled),
+            ("A ->> B", LineStyle::Solid, HeadStyle::Open),
+            ("A -->> B", LineStyle::Dashed, HeadStyle::Open),
+        ];
+        for (line, expected_line, expected_head) in cases {
+            assert_eq!(
+                LineContents::Interaction(
+                    FromParticipant("A".to_string()),
+                    ToParticipant("B".to_string()),
+                    ArrowStyle {
+                        line: expected_line,
+                        head: expected_head,
+                        activation: None,
+                    }
+                ),
+                DocumentParser::parse_interaction(line),
+                "{}",
+                line
+            );
+        }
+    }
+
     #[test]
     fn test_parse_interaction_no_match() {
         assert_eq!(
@@ -196,7 +919,7 @@ mod tests {
     Server
     -> Server: Response";
         let sss = str_to_vec(text);
-        let doc = DocumentParser::parse(&sss, make_config());
+        let doc = DocumentParser::parse(&sss, make_config()).unwrap();
         assert_eq!(3, doc.lines.len());
 
         assert_eq!(0, doc.lines[0].line_number);
@@ -204,6 +927,7 @@ mod tests {
             LineContents::InteractionWithMessage(
                 FromParticipant("Client".to_string()),
                 ToParticipant("Server".to_string()),
+                ArrowStyle::default(),
                 InteractionMessage("Message".to_string())
             ),
             doc.lines[0].line_contents
@@ -224,7 +948,7 @@ mod tests {
     Server -> Database
     Database -> Server: Response";
         let sss = str_to_vec(text);
-        let doc = DocumentParser::parse(&sss, make_config());
+        let doc = DocumentParser::parse(&sss, make_config()).unwrap();
         assert_eq!(5, doc.lines.len());
 
         assert_eq!(0, doc.lines[0].line_number);
@@ -243,6 +967,7 @@ mod tests {
             LineContents::InteractionWithMessage(
                 FromParticipant("Client".to_string()),
                 ToParticipant("Server".to_string()),
+                ArrowStyle::default(),
                 InteractionMessage("Message".to_string())
             ),
             doc.lines[2].line_contents
@@ -253,7 +978,8 @@ mod tests {
         assert_eq!(
             LineContents::Interaction(
                 FromParticipant("Server".to_string()),
-                ToParticipant("Database".to_string())
+                ToParticipant("Database".to_string()),
+                ArrowStyle::default()
             ),
             doc.lines[3].line_contents
         );
@@ -264,6 +990,7 @@ mod tests {
             LineContents::InteractionWithMessage(
                 FromParticipant("Database".to_string()),
                 ToParticipant("Server".to_string()),
+                ArrowStyle::default(),
                 InteractionMessage("Response".to_string())
             ),
             doc.lines[4].line_contents
@@ -274,13 +1001,14 @@ mod tests {
     #[test]
     fn test_document_parser_comment_lines() {
         let text = "# this is a comment\nClient -> Server";
-        let doc = DocumentParser::parse(&str_to_vec(text), make_config());
+        let doc = DocumentParser::parse(&str_to_vec(text), make_config()).unwrap();
         assert_eq!(2, doc.lines.len());
         assert_eq!(LineContents::Comment, doc.lines[0].line_contents);
         assert_eq!(
             LineContents::Interaction(
                 FromParticipant("Client".to_string()),
-                ToParticipant("Server".to_string())
+                ToParticipant("Server".to_string()),
+                ArrowStyle::default()
             ),
             doc.lines[1].line_contents
         );
@@ -288,7 +1016,548 @@ mod tests {
 
     #[test]
     fn test_document_is_valid() {
-        let doc = DocumentParser::parse(&str_to_vec("Client -> Server"), make_config());
+        let doc = DocumentParser::parse(&str_to_vec("Client -> Server"), make_config()).unwrap();
+        assert!(doc.is_valid);
+    }
+
+    #[test]
+    fn test_document_parser_empty_input() {
+        assert!(matches!(
+            DocumentParser::parse(&str_to_vec("\n   \n"), make_config()),
+            Err(Error::EmptyDocument)
+        ));
+        assert!(matches!(
+            DocumentParser::parse(&[], make_config()),
+            Err(Error::EmptyDocument)
+        ));
+    }
+
+    #[test]
+    fn test_theme_name_uses_last_theme_line() {
+        let doc = DocumentParser::parse(
+            &str_to_vec(":theme Dark\n:theme Monochrome\nClient -> Server"),
+            make_config(),
+        )
+        .unwrap();
+        assert_eq!(Some("Monochrome"), doc.theme_name());
+
+        let doc = DocumentParser::parse(&str_to_vec("Client -> Server"), make_config()).unwrap();
+        assert_eq!(None, doc.theme_name());
+    }
+
+    #[test]
+    fn test_diagnostics_unknown_theme() {
+        assert!(diagnostics(":theme high-contrast\nA -> B").is_empty());
+        assert!(diagnostics(":theme file:brand.toml\nA -> B").is_empty());
+
+        let diagnostics = diagnostics(":theme  Neon\nA -> B");
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!(8..12, diagnostics[0].columns);
+        assert_eq!(
+            "unknown theme `Neon`, using the default theme",
+            diagnostics[0].reason
+        );
+    }
+
+    fn diagnostics(text: &str) -> Vec<Diagnostic> {
+        parse_example(text).diagnostics
+    }
+
+    #[test]
+    fn test_diagnostics_unknown_metadata_key() {
+        let diagnostics = diagnostics(":colour red\nClient -> Server");
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!(0, diagnostics[0].line_number);
+        assert_eq!(0..7, diagnostics[0].columns);
+        assert_eq!("unknown metadata key `:colour`", diagnostics[0].reason);
+    }
+
+    #[test]
+    fn test_diagnostics_malformed_arrow() {
+        let diagnostics = diagnostics("Client -> Server\n  Server => Client");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!(1, diagnostics[0].line_number);
+        assert_eq!(9..11, diagnostics[0].columns);
+        assert_eq!("malformed arrow `=>`", diagnostics[0].reason);
+    }
+
+    #[test]
+    fn test_diagnostics_missing_participants() {
+        let diagnostics = diagnostics("Client ->\n-> Server\nClient->Server");
+        assert_eq!(3, diagnostics.len());
+        assert_eq!("missing target participant", diagnostics[0].reason);
+        assert_eq!(7..9, diagnostics[0].columns);
+        assert_eq!("missing source participant", diagnostics[1].reason);
+        assert_eq!(0..2, diagnostics[1].columns);
+        assert_eq!(
+            "arrow `->` needs a space on both sides",
+            diagnostics[2].reason
+        );
+        assert_eq!(6..8, diagnostics[2].columns);
+    }
+
+    #[test]
+    fn test_diagnostics_blank_target_participant() {
+        let diagnostics = diagnostics("A ->  : x");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!("missing target participant", diagnostics[0].reason);
+        assert_eq!(2..4, diagnostics[0].columns);
+    }
+
+    #[test]
+    fn test_document_is_valid_with_warnings_only() {
+        let doc = DocumentParser::parse(&str_to_vec(":foo bar\nClient -> Server"), make_config())
+            .unwrap();
         assert!(doc.is_valid);
+
+        let doc = DocumentParser::parse(&str_to_vec("Client ->\nClient -> Server"), make_config())
+            .unwrap();
+        assert!(!doc.is_valid);
+    }
+
+    #[test]
+    fn test_parse_declaration() {
+        let declaration = |display_name: &str, alias: Option<&str>| {
+            Some(LineContents::Participant(ParticipantDeclaration {
+                kind: ParticipantKind::Participant,
+                display_name: display_name.to_string(),
+                alias: alias.map(str::to_string),
+            }))
+        };
+        assert_eq!(
+            declaration("Server", None),
+            DocumentParser::parse_declaration("participant Server")
+        );
+        assert_eq!(
+            declaration("Server", Some("S")),
+            DocumentParser::parse_declaration("participant  Server as S")
+        );
+        assert_eq!(
+            declaration("Order Service: v2 -> EU", Some("OS")),
+            DocumentParser::parse_declaration("participant \"Order Service: v2 -> EU\" as OS")
+        );
+        assert_eq!(
+            None,
+            DocumentParser::parse_declaration("participant Order Service")
+        );
+        assert_eq!(None, DocumentParser::parse_declaration("participant"));
+    }
+
+    #[test]
+    fn test_parse_declaration_kinds() {
+        for kind in ParticipantKind::ALL {
+            let line = format!("{} \"Some One\" as S", kind.keyword());
+            assert_eq!(
+                Some(LineContents::Participant(ParticipantDeclaration {
+                    kind,
+                    display_name: "Some One".to_string(),
+                    alias: Some("S".to_string()),
+                })),
+                DocumentParser::parse_declaration(&line),
+                "{}",
+                line
+            );
+        }
+        assert_eq!(None, DocumentParser::parse_declaration("robot R2D2"));
+    }
+
+    #[test]
+    fn test_document_parser_declaration_with_arrow_in_name() {
+        let doc = DocumentParser::parse(
+            &str_to_vec("participant \"A -> B\" as AB\nparticipant -> AB"),
+            make_config(),
+        )
+        .unwrap();
+        assert!(doc.is_valid);
+        assert!(matches!(
+            doc.lines[0].line_contents,
+            LineContents::Participant(_)
+        ));
+        assert_eq!(
+            LineContents::Interaction(
+                FromParticipant("participant".to_string()),
+                ToParticipant("AB".to_string()),
+                ArrowStyle::default()
+            ),
+            doc.lines[1].line_contents
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_malformed_declaration() {
+        let diagnostics = diagnostics("participant \"Order Service as OS\nOS -> DB");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!("malformed participant declaration", diagnostics[0].reason);
+        assert_eq!(0..32, diagnostics[0].columns);
+    }
+
+    #[test]
+    fn test_diagnostics_declaration_order() {
+        let diagnostics =
+            diagnostics("participant A\nA -> B\nparticipant B\nparticipant \"Alpha\" as A");
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!(2, diagnostics[0].line_number);
+        assert_eq!(
+            "participant `B` is declared after it is used",
+            diagnostics[0].reason
+        );
+        assert_eq!(3, diagnostics[1].line_number);
+        assert_eq!("participant `A` is already declared", diagnostics[1].reason);
+    }
+
+    #[test]
+    fn test_diagnostics_declaration_columns_include_indent() {
+        let diagnostics = diagnostics("A -> B\n  participant B\n\tparticipant B");
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(2..15, diagnostics[0].columns);
+        assert_eq!(1..14, diagnostics[1].columns);
+    }
+
+    #[test]
+    fn test_parse_metadata_footbox() {
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Footbox(true)),
+            DocumentParser::parse_metadata(":footbox on")
+        );
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Footbox(false)),
+            DocumentParser::parse_metadata(":footbox OFF")
+        );
+
+        let doc = DocumentParser::parse(
+            &str_to_vec(":footbox on\n:footbox off\nA -> B"),
+            make_config(),
+        )
+        .unwrap();
+        assert_eq!(Some(false), doc.footbox());
+    }
+
+    #[test]
+    fn test_diagnostics_invalid_footbox() {
+        let diagnostics = diagnostics(":footbox  maybe\nA -> B");
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!(10..15, diagnostics[0].columns);
+        assert_eq!("invalid `:footbox` value `maybe`", diagnostics[0].reason);
+    }
+
+    #[test]
+    fn test_parse_note() {
+        let note = |placement: NotePlacement, participants: &[&str], text: &[&str]| {
+            Some(LineContents::Note(NoteLine {
+                placement,
+                participants: participants.iter().map(|p| p.to_string()).collect(),
+                text: text.iter().map(|t| t.to_string()).collect(),
+            }))
+        };
+        assert_eq!(
+            note(NotePlacement::LeftOf, &["A"], &["retries -> 3"]),
+            DocumentParser::parse_note("note left of A: retries -> 3")
+        );
+        assert_eq!(
+            note(
+                NotePlacement::RightOf,
+                &["Order Service"],
+                &["first", "second"]
+            ),
+            DocumentParser::parse_note("note right of Order Service:first\\n second")
+        );
+        assert_eq!(
+            note(NotePlacement::Over, &["A", "B"], &["both: yes"]),
+            DocumentParser::parse_note("note over A , B: both: yes")
+        );
+        assert_eq!(None, DocumentParser::parse_note("note left of A, B: text"));
+        assert_eq!(None, DocumentParser::parse_note("note over A"));
+    }
+
+    #[test]
+    fn test_diagnostics_malformed_note() {
+        let diagnostics = diagnostics("A -> B\nnote above A: text");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!("malformed note", diagnostics[0].reason);
+        assert_eq!(1, diagnostics[0].line_number);
+    }
+
+    #[test]
+    fn test_parse_fragment() {
+        assert_eq!(
+            Some(LineContents::FragmentStart(
+                FragmentKind::Loop,
+                Some("every 5s".to_string())
+            )),
+            DocumentParser::parse_fragment("loop every 5s")
+        );
+        assert_eq!(
+            Some(LineContents::FragmentStart(FragmentKind::Opt, None)),
+            DocumentParser::parse_fragment("opt")
+        );
+        assert_eq!(
+            Some(LineContents::FragmentElse(Some("failure".to_string()))),
+            DocumentParser::parse_fragment("else  failure")
+        );
+        assert_eq!(
+            Some(LineContents::FragmentEnd),
+            DocumentParser::parse_fragment("end")
+        );
+        assert_eq!(None, DocumentParser::parse_fragment("loop -> B"));
+        assert_eq!(None, DocumentParser::parse_fragment("end of story"));
+
+        let doc = DocumentParser::parse(&str_to_vec("loop -> B"), make_config()).unwrap();
+        assert!(matches!(
+            doc.lines[0].line_contents,
+            LineContents::Interaction(..)
+        ));
+    }
+
+    #[test]
+    fn test_diagnostics_unbalanced_fragments() {
+        let diagnostics = diagnostics("end\nloop\nA -> B\nelse\nend\nalt ok\nA -> B");
+        assert_eq!(3, diagnostics.len());
+        assert!(diagnostics.iter().all(|d| d.is_error()));
+        assert_eq!(0, diagnostics[0].line_number);
+        assert_eq!("`end` without a matching fragment", diagnostics[0].reason);
+        assert_eq!(3, diagnostics[1].line_number);
+        assert_eq!(
+            "`loop` fragments cannot be split with `else`",
+            diagnostics[1].reason
+        );
+        assert_eq!(5, diagnostics[2].line_number);
+        assert_eq!(0..3, diagnostics[2].columns);
+        assert_eq!("`alt` fragment is never closed", diagnostics[2].reason);
+    }
+
+    #[test]
+    fn test_diagnostics_fragment_columns_include_indent() {
+        let diagnostics = diagnostics("  end\nloop\n  A -> B\n    else\nend\n  opt\nA -> B");
+        assert_eq!(3, diagnostics.len());
+        assert_eq!(2..5, diagnostics[0].columns);
+        assert_eq!(4..8, diagnostics[1].columns);
+        assert_eq!(2..5, diagnostics[2].columns);
+    }
+
+    #[test]
+    fn test_diagnostics_else_outside_fragment() {
+        let diagnostics = diagnostics("A -> B\nelse\nalt\nelse\nend");
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(1, diagnostics[0].line_number);
+        assert_eq!("`else` outside of a fragment", diagnostics[0].reason);
+    }
+
+    #[test]
+    fn test_parse_activation() {
+        assert_eq!(
+            Some(LineContents::Activation(
+                ActivationChange::Activate,
+                "Order Service".to_string()
+            )),
+            DocumentParser::parse_lifeline("activate  Order Service")
+        );
+        assert_eq!(
+            Some(LineContents::Activation(
+                ActivationChange::Deactivate,
+                "B".to_string()
+            )),
+            DocumentParser::parse_lifeline("deactivate B")
+        );
+        assert_eq!(None, DocumentParser::parse_lifeline("activate -> B"));
+        assert_eq!(None, DocumentParser::parse_lifeline("activate"));
+
+        let doc = DocumentParser::parse(&str_to_vec("activate -> B"), make_config()).unwrap();
+        assert!(matches!(
+            doc.lines[0].line_contents,
+            LineContents::Interaction(..)
+        ));
+    }
+
+    #[test]
+    fn test_parse_interaction_activation_shorthand() {
+        let style = |activation| ArrowStyle {
+            line: LineStyle::Dashed,
+            activation,
+            ..Default::default()
+        };
+        assert_eq!(
+            LineContents::InteractionWithMessage(
+                FromParticipant("A".to_string()),
+                ToParticipant("B".to_string()),
+                style(Some(ActivationChange::Activate)),
+                InteractionMessage("call".to_string())
+            ),
+            DocumentParser::parse_interaction("A --> +B: call")
+        );
+        assert_eq!(
+            LineContents::Interaction(
+                FromParticipant("B".to_string()),
+                ToParticipant("A".to_string()),
+                style(Some(ActivationChange::Deactivate))
+            ),
+            DocumentParser::parse_interaction("B --> - A")
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_unbalanced_activations() {
+        let diagnostics = diagnostics("A -> +B\ndeactivate A\nactivate\nB --> -A\nactivate C");
+        assert_eq!(3, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!(1, diagnostics[0].line_number);
+        assert_eq!(
+            "participant `A` is deactivated but not active",
+            diagnostics[0].reason
+        );
+        assert!(diagnostics[1].is_error());
+        assert_eq!("malformed activation", diagnostics[1].reason);
+        assert_eq!(Severity::Warning, diagnostics[2].severity);
+        assert_eq!(4, diagnostics[2].line_number);
+        assert_eq!(
+            "participant `C` is never deactivated",
+            diagnostics[2].reason
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_activation_marker_without_target() {
+        let diagnostics = diagnostics("A -> + : x");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].is_error());
+        assert_eq!("missing target participant", diagnostics[0].reason);
+        assert_eq!(2..4, diagnostics[0].columns);
+    }
+
+    #[test]
+    fn test_diagnostics_activation_columns_include_indent() {
+        let diagnostics = diagnostics("A -> B\n  deactivate A\n    activate B");
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(2..14, diagnostics[0].columns);
+        assert_eq!(4..14, diagnostics[1].columns);
+    }
+
+    #[test]
+    fn test_parse_create_and_destroy() {
+        assert_eq!(
+            Some(LineContents::Create("Worker".to_string())),
+            DocumentParser::parse_lifeline("create Worker")
+        );
+        assert_eq!(
+            Some(LineContents::Destroy("Worker Pool".to_string())),
+            DocumentParser::parse_lifeline("destroy Worker Pool")
+        );
+        assert_eq!(None, DocumentParser::parse_lifeline("create -> Worker"));
+    }
+
+    #[test]
+    fn test_diagnostics_create_and_destroy() {
+        let diagnostics =
+            diagnostics("A -> B\ncreate B\ncreate W\nW -> A\ndestroy W\nA -> W\ndestroy W\ncreate");
+        let reasons = diagnostics
+            .iter()
+            .map(|d| (d.line_number, d.reason.as_str()))
+            .collect_vec();
+        assert_eq!(
+            vec![
+                (1, "participant `B` is created after it is used"),
+                (3, "participant `W` sends a message before receiving one"),
+                (5, "participant `W` is used after it is destroyed"),
+                (6, "participant `W` is already destroyed"),
+                (7, "malformed `create` line"),
+            ],
+            reasons
+        );
+        assert_eq!(Severity::Warning, diagnostics[1].severity);
+    }
+
+    #[test]
+    fn test_diagnostics_lifeline_columns_include_indent() {
+        let diagnostics = diagnostics("  create W\n    W -> A\n  destroy W\n\tdestroy W");
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(4..10, diagnostics[0].columns);
+        assert_eq!(1..10, diagnostics[1].columns);
+    }
+
+    #[test]
+    fn test_parse_metadata_autonumber() {
+        let start = |start, step, pattern: Option<&str>, hierarchical| {
+            LineContents::MetaData(MetaDataType::AutoNumber(AutoNumber::Start(NumberFormat {
+                start,
+                step,
+                pattern: pattern.map(str::to_owned),
+                hierarchical,
+            })))
+        };
+        assert_eq!(
+            start(1, 1, None, false),
+            DocumentParser::parse_metadata(":autonumber")
+        );
+        assert_eq!(
+            start(10, 5, Some("[000]"), false),
+            DocumentParser::parse_metadata(":autonumber 10 5 \"[000]\"")
+        );
+        assert_eq!(
+            start(3, 1, None, true),
+            DocumentParser::parse_metadata(":autonumber 3 hierarchical")
+        );
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::AutoNumber(AutoNumber::Stop)),
+            DocumentParser::parse_metadata(":autonumber stop")
+        );
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Invalid),
+            DocumentParser::parse_metadata(":autonumber 1 0")
+        );
+        assert_eq!(
+            start(1_000_000, 1_000_000, None, false),
+            DocumentParser::parse_metadata(":autonumber 1000000 1000000")
+        );
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Invalid),
+            DocumentParser::parse_metadata(":autonumber 18446744073709551615 18446744073709551615")
+        );
+        assert_eq!(
+            LineContents::MetaData(MetaDataType::Invalid),
+            DocumentParser::parse_metadata(":autonumber 1 1000001")
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_autonumber() {
+        let diagnostics = diagnostics(
+            ":autonumber resume\n:autonumber ten\nA -> B\n:autonumber\n:autonumber stop",
+        );
+        assert_eq!(2, diagnostics.len());
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert_eq!(
+            "`:autonumber resume` before numbering is started",
+            diagnostics[0].reason
+        );
+        assert_eq!("invalid `:autonumber` value `ten`", diagnostics[1].reason);
+        assert_eq!(12..15, diagnostics[1].columns);
+    }
+
+    #[test]
+    fn test_diagnostics_autonumber_out_of_range() {
+        let diagnostics = diagnostics(concat!(
+            ":autonumber 18446744073709551615 18446744073709551615\n",
+            "A -> B\nB -> A"
+        ));
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!(
+            "invalid `:autonumber` value `18446744073709551615 18446744073709551615`",
+            diagnostics[0].reason
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_autonumber_columns_include_indent() {
+        let diagnostics = diagnostics("  :autonumber stop\nA -> B");
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(2..18, diagnostics[0].columns);
     }
 }
@@ -0,0 +1,182 @@
+use log::info;
+
+use crate::model::{Fragment, Line, LineContents, Operand, ParticipantId, Participants};
+
+// == Fragment Parser =====================================
+#[derive(Debug, Default)]
+pub struct FragmentParser;
+
+impl FragmentParser {
+    /// Nest the fragment lines into a tree of [`Fragment`]s, noting which
+    /// participants each one covers.
+    ///
+    /// Stray `end` and `else` lines are ignored, and fragments still open at
+    /// the end of the input are closed there; both are reported as
+    /// diagnostics by the `DocumentParser`.
+    pub fn parse(document: &[Line], participants: &Participants) -> Vec<Fragment> {
+        let mut roots = vec![];
+        let mut open: Vec<Fragment> = vec![];
+
+        for line in document {
+            match &line.line_contents {
+                LineContents::FragmentStart(kind, guard) => open.push(Fragment {
+                    kind: *kind,
+                    line_number: line.line_number,
+                    end_line_number: line.line_number,
+                    operands: vec![Operand {
+                        line_number: line.line_number,
+                        guard: guard.clone(),
+                        fragments: vec![],
+                    }],
+                    participants: None,
+                }),
+                LineContents::FragmentElse(guard) => {
+                    if let Some(fragment) = open.last_mut() {
+                        fragment.operands.push(Operand {
+                            line_number: line.line_number,
+                            guard: guard.clone(),
+                            fragments: vec![],
+                        });
+                    }
+                }
+                LineContents::FragmentEnd => {
+                    if let Some(mut fragment) = open.pop() {
+                        fragment.end_line_number = line.line_number;
+                        Self::close(fragment, &mut open, &mut roots);
+                    }
+                }
+                LineContents::Interaction(f, t, _)
+                | LineContents::InteractionWithMessage(f, t, _, _) => {
+                    Self::cover(&mut open, participants, [&f.0, &t.0]);
+                }
+                LineContents::Note(note) => {
+                    Self::cover(&mut open, participants, &note.participants);
+                }
+                _ => {}
+            }
+        }
+        while let Some(mut fragment) = open.pop() {
+            fragment.end_line_number = document.len();
+            Self::close(fragment, &mut open, &mut roots);
+        }
+
+        info!("Fragments: {:#?}", roots);
+        roots
+    }
+
+    /// Widens the innermost open fragment to cover the named participants.
+    fn cover<'n>(
+        open: &mut [Fragment],
+        participants: &Participants,
+        names: impl IntoIterator<Item = &'n String>,
+    ) {
+        if let Some(fragment) = open.last_mut() {
+            for id in names.into_iter().filter_map(|name| participants.id(name)) {
+                fragment.participants = Some(span(fragment.participants, (id, id)));
+            }
+        }
+    }
+
+    /// Adds a finished fragment to the operand it is nested in, widening its
+    /// parent to cover it, or to the roots when it is not nested.
+    fn close(fragment: Fragment, open: &mut [Fragment], roots: &mut Vec<Fragment>) {
+        match open.last_mut() {
+            Some(parent) => {
+                if let Some(covered) = fragment.participants {
+                    parent.participants = Some(span(parent.participants, covered));
+                }
+                parent
+                    .operands
+                    .last_mut()
+                    .expect("fragments start with an operand")
+                    .fragments
+                    .push(fragment);
+            }
+            None => roots.push(fragment),
+        }
+    }
+}
+
+fn span(
+    current: Option<(ParticipantId, ParticipantId)>,
+    (first, last): (ParticipantId, ParticipantId),
+) -> (ParticipantId, ParticipantId) {
+    match current {
+        Some((from, to)) => (from.min(first), to.max(last)),
+        None => (first, last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FragmentKind;
+    use crate::parsing::document::parse_example;
+    use crate::parsing::participant::ParticipantParser;
+
+    fn parse(text: &str) -> (Participants, Vec<Fragment>) {
+        let document = parse_example(text);
+        let participants = ParticipantParser::parse(&document.lines);
+        let fragments = FragmentParser::parse(&document.lines, &participants);
+        (participants, fragments)
+    }
+
+    #[test]
+    fn test_parse_alt_with_else() {
+        let (participants, fragments) =
+            parse("A -> B\nalt success\nB -> C\nelse failure\nB --> A\nend\nA -> D");
+        assert_eq!(1, fragments.len());
+
+        let alt = &fragments[0];
+        assert_eq!(FragmentKind::Alt, alt.kind);
+        assert_eq!(1, alt.line_number);
+        assert_eq!(5, alt.end_line_number);
+        assert_eq!(
+            vec![Some("success"), Some("failure")],
+            alt.operands
+                .iter()
+                .map(|o| o.guard.as_deref())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, alt.operands[1].line_number);
+        assert_eq!(
+            Some((participants.id("A").unwrap(), participants.id("C").unwrap())),
+            alt.participants
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_fragments() {
+        let (participants, fragments) =
+            parse("loop every 5s\nA -> B\nopt\nB -> C\nend\nend\ncritical\nend");
+        assert_eq!(2, fragments.len());
+
+        let outer = &fragments[0];
+        assert_eq!(FragmentKind::Loop, outer.kind);
+        assert_eq!(1, outer.operands.len());
+        let inner = &outer.operands[0].fragments;
+        assert_eq!(1, inner.len());
+        assert_eq!(FragmentKind::Opt, inner[0].kind);
+        assert_eq!(None, inner[0].operands[0].guard);
+        assert_eq!(
+            Some((participants.id("B").unwrap(), participants.id("C").unwrap())),
+            inner[0].participants
+        );
+        // the outer loop covers its nested fragment's participants
+        assert_eq!(
+            Some((participants.id("A").unwrap(), participants.id("C").unwrap())),
+            outer.participants
+        );
+
+        assert_eq!(FragmentKind::Critical, fragments[1].kind);
+        assert_eq!(None, fragments[1].participants);
+    }
+
+    #[test]
+    fn test_parse_unbalanced_fragments() {
+        let (_, fragments) = parse("end\nA -> B\nbreak\nA -> B");
+        assert_eq!(1, fragments.len());
+        assert_eq!(FragmentKind::Break, fragments[0].kind);
+        assert_eq!(4, fragments[0].end_line_number);
+    }
+}
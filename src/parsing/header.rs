@@ -0,0 +1,91 @@
+use chrono::Local;
+use log::info;
+
+use crate::model::{Header, Line, LineContents, MetaDataType};
+
+// == Header Parser =======================================
+#[derive(Debug, Default)]
+pub struct HeaderParser;
+
+impl HeaderParser {
+    /// Collect the `:title`, `:author` and `:date` metadata into a [`Header`].
+    ///
+    /// A bare `:date` is filled in with today's date. If a key appears more
+    /// than once the last value wins.
+    pub fn parse(document: &[Line]) -> Header {
+        let mut header = Header::default();
+
+        document
+            .iter()
+            .filter_map(|line| match &line.line_contents {
+                LineContents::MetaData(meta) => Some(meta),
+                _ => None,
+            })
+            .for_each(|meta| match meta {
+                MetaDataType::Title(title) => header.title = Some(title.clone()),
+                MetaDataType::Author(author) => header.author = Some(author.clone()),
+                MetaDataType::Date(Some(date)) => header.date = Some(date.clone()),
+                MetaDataType::Date(None) => {
+                    header.date = Some(Local::now().format("%Y-%m-%d").to_string())
+                }
+                _ => {}
+            });
+
+        info!("Header: {:?}", header);
+        header
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_line(line_contents: LineContents) -> Line {
+        Line {
+            line_contents,
+            line_data: String::new(),
+            line_number: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_header_fields() {
+        let document = vec![
+            make_line(LineContents::MetaData(MetaDataType::Title(
+                "Example".to_string(),
+            ))),
+            make_line(LineContents::Comment),
+            make_line(LineContents::MetaData(MetaDataType::Author(
+                "Alice".to_string(),
+            ))),
+            make_line(LineContents::MetaData(MetaDataType::Date(Some(
+                "2021-03-04".to_string(),
+            )))),
+        ];
+        let header = HeaderParser::parse(&document);
+        assert_eq!(
+            Header {
+                title: Some("Example".to_string()),
+                author: Some("Alice".to_string()),
+                date: Some("2021-03-04".to_string()),
+            },
+            header
+        );
+    }
+
+    #[test]
+    fn test_parse_header_bare_date_is_today() {
+        let document = vec![make_line(LineContents::MetaData(MetaDataType::Date(None)))];
+        let header = HeaderParser::parse(&document);
+        assert_eq!(
+            Some(Local::now().format("%Y-%m-%d").to_string()),
+            header.date
+        );
+    }
+
+    #[test]
+    fn test_parse_header_empty() {
+        let document = vec![make_line(LineContents::Empty)];
+        assert_eq!(Header::default(), HeaderParser::parse(&document));
+    }
+}
@@ -1,9 +1,11 @@
-use std::collections::HashSet;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use log::info;
 
-use crate::model::{Interaction, InteractionType, Line, LineContents, Message, Participant};
+use crate::error::{Error, Result};
+use crate::model::{
+    Interaction, InteractionType, Line, LineContents, Message, ParticipantId, Participants,
+};
 use crate::InteractionSet;
 
 // == Interaction Parser ==================================
@@ -11,19 +13,25 @@ use crate::InteractionSet;
 pub struct InteractionParser;
 
 impl InteractionParser {
-    fn interaction_type(from: &Participant, to: &Participant) -> InteractionType {
-        match from.index.cmp(&to.index) {
+    fn interaction_type(from: ParticipantId, to: ParticipantId) -> InteractionType {
+        match from.cmp(&to) {
             std::cmp::Ordering::Less => InteractionType::L2R,
             std::cmp::Ordering::Equal => InteractionType::SelfRef,
             std::cmp::Ordering::Greater => InteractionType::R2L,
         }
     }
 
+    fn find(participants: &Participants, name: &str) -> Result<ParticipantId> {
+        participants
+            .id(name)
+            .ok_or_else(|| Error::UnknownParticipant(name.to_owned()))
+    }
+
     /// Parse interaction lines from a parsed document.
     ///
     /// For each `Interaction` or `InteractionWithMessage` line, looks up the
     /// corresponding participants and builds an [`Interaction`] value.
-    pub fn parse(document: &[Line], participants: &HashSet<Participant>) -> InteractionSet {
+    pub fn parse(document: &[Line], participants: &Participants) -> Result<InteractionSet> {
         info!("InteractionParser.parse({:#?})", document);
 
         let interaction_index = AtomicU32::new(0);
@@ -32,33 +40,40 @@ impl InteractionParser {
             .filter(|line| {
                 matches!(
                     line.line_contents,
-                    LineContents::Interaction(_, _) | LineContents::InteractionWithMessage(_, _, _)
+                    LineContents::Interaction(_, _, _)
+                        | LineContents::InteractionWithMessage(_, _, _, _)
                 )
             })
             .map(|line| match &line.line_contents {
-                LineContents::Interaction(f, t) => {
-                    info!("I: {:?}, {:?}", f, t);
-                    let from_p = participants.iter().find(|p| p.name == f.0).unwrap();
-                    let to_p = participants.iter().find(|p| p.name == t.0).unwrap();
-                    Interaction {
+                LineContents::Interaction(f, t, a) => {
+                    info!("I: {:?}, {:?}, {:?}", f, t, a);
+                    let from_p = Self::find(participants, &f.0)?;
+                    let to_p = Self::find(participants, &t.0)?;
+                    Ok(Interaction {
                         index: interaction_index.fetch_add(1, Ordering::Relaxed),
-                        from_participant: from_p.to_owned(),
-                        to_participant: to_p.to_owned(),
+                        line_number: line.line_number,
+                        from_participant: from_p,
+                        to_participant: to_p,
                         interaction_type: Self::interaction_type(from_p, to_p),
+                        arrow_style: *a,
                         message: None,
-                    }
+                        number: None,
+                    })
                 }
-                LineContents::InteractionWithMessage(f, t, m) => {
-                    info!("IwM: {:?}, {:?}, {:?}", f, t, m);
-                    let from_p = participants.iter().find(|p| p.name == f.0).unwrap();
-                    let to_p = participants.iter().find(|p| p.name == t.0).unwrap();
-                    Interaction {
+                LineContents::InteractionWithMessage(f, t, a, m) => {
+                    info!("IwM: {:?}, {:?}, {:?}, {:?}", f, t, a, m);
+                    let from_p = Self::find(participants, &f.0)?;
+                    let to_p = Self::find(participants, &t.0)?;
+                    Ok(Interaction {
                         index: interaction_index.fetch_add(1, Ordering::Relaxed),
-                        from_participant: from_p.clone(),
-                        to_participant: to_p.clone(),
+                        line_number: line.line_number,
+                        from_participant: from_p,
+                        to_participant: to_p,
                         interaction_type: Self::interaction_type(from_p, to_p),
+                        arrow_style: *a,
                         message: Some(Message(m.0.clone())),
-                    }
+                        number: None,
+                    })
                 }
                 _ => unreachable!("filter above only allows Interaction variants"),
             })
@@ -70,22 +85,16 @@ impl InteractionParser {
 mod tests {
     use super::*;
     use crate::model::{
-        FromParticipant, InteractionMessage, LineContents, ToParticipant,
+        ArrowStyle, FromParticipant, HeadStyle, InteractionMessage, LineContents, LineStyle,
+        ToParticipant,
     };
-    use crate::rendering::Rect;
-
-    fn make_rect() -> Rect {
-        Rect { x: 0, y: 0, w: 0, h: 0 }
-    }
 
-    fn make_participant(name: &str, index: usize) -> Participant {
-        Participant {
-            name: name.to_string(),
-            index,
-            active_from: 0,
-            active_to: 0,
-            rect: make_rect(),
-        }
+    fn make_participants(names: &[&str]) -> Participants {
+        let mut participants = Participants::default();
+        names.iter().for_each(|name| {
+            participants.insert(name, 0);
+        });
+        participants
     }
 
     #[test]
@@ -94,22 +103,27 @@ mod tests {
             line_contents: LineContents::Interaction(
                 FromParticipant("Client".to_owned()),
                 ToParticipant("Server".to_owned()),
+                ArrowStyle::default(),
             ),
             line_data: "Client -> Server".to_owned(),
             line_number: 0,
         }];
 
-        let mut participants = HashSet::new();
-        participants.insert(make_participant("Client", 0));
-        participants.insert(make_participant("Server", 1));
+        let participants = make_participants(&["Client", "Server"]);
 
-        let inters = InteractionParser::parse(&document, &participants);
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
         assert_eq!(1, inters.len());
 
         let interaction = inters.first().unwrap();
         assert_eq!(0, interaction.index);
-        assert_eq!("Client", interaction.from_participant.name);
-        assert_eq!("Server", interaction.to_participant.name);
+        assert_eq!(
+            participants.id("Client").unwrap(),
+            interaction.from_participant
+        );
+        assert_eq!(
+            participants.id("Server").unwrap(),
+            interaction.to_participant
+        );
         assert_eq!(None, interaction.message);
         assert_eq!(InteractionType::L2R, interaction.interaction_type);
     }
@@ -121,6 +135,7 @@ mod tests {
                 line_contents: LineContents::Interaction(
                     FromParticipant("Client".to_owned()),
                     ToParticipant("Server".to_owned()),
+                    ArrowStyle::default(),
                 ),
                 line_data: "Client -> Server".to_owned(),
                 line_number: 0,
@@ -129,17 +144,16 @@ mod tests {
                 line_contents: LineContents::Interaction(
                     FromParticipant("Server".to_owned()),
                     ToParticipant("Client".to_owned()),
+                    ArrowStyle::default(),
                 ),
                 line_data: "Server -> Client".to_owned(),
                 line_number: 1,
             },
         ];
 
-        let mut participants = HashSet::new();
-        participants.insert(make_participant("Client", 0));
-        participants.insert(make_participant("Server", 1));
+        let participants = make_participants(&["Client", "Server"]);
 
-        let inters = InteractionParser::parse(&document, &participants);
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
         assert_eq!(2, inters.len());
 
         let mut sorted = inters;
@@ -155,21 +169,21 @@ mod tests {
             line_contents: LineContents::InteractionWithMessage(
                 FromParticipant("Client".to_owned()),
                 ToParticipant("Client".to_owned()),
+                ArrowStyle::default(),
                 InteractionMessage("Processing".to_owned()),
             ),
             line_data: "Client -> Client: Processing".to_owned(),
             line_number: 0,
         }];
 
-        let mut participants = HashSet::new();
-        participants.insert(make_participant("Client", 0));
+        let participants = make_participants(&["Client"]);
 
-        let inters = InteractionParser::parse(&document, &participants);
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
         assert_eq!(1, inters.len());
 
         let interaction = inters.first().unwrap();
-        assert_eq!("Client", interaction.from_participant.name);
-        assert_eq!("Client", interaction.to_participant.name);
+        assert_eq!(ParticipantId(0), interaction.from_participant);
+        assert_eq!(ParticipantId(0), interaction.to_participant);
         assert_eq!(
             Some(Message("Processing".to_string())),
             interaction.message
@@ -183,17 +197,16 @@ mod tests {
             line_contents: LineContents::InteractionWithMessage(
                 FromParticipant("A".to_owned()),
                 ToParticipant("B".to_owned()),
+                ArrowStyle::default(),
                 InteractionMessage("hello".to_owned()),
             ),
             line_data: "A -> B: hello".to_owned(),
             line_number: 0,
         }];
 
-        let mut participants = HashSet::new();
-        participants.insert(make_participant("A", 0));
-        participants.insert(make_participant("B", 1));
+        let participants = make_participants(&["A", "B"]);
 
-        let inters = InteractionParser::parse(&document, &participants);
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
         assert_eq!(1, inters.len());
         assert_eq!(
             Some(Message("hello".to_string())),
@@ -201,6 +214,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_interaction_parser_keeps_arrow_style() {
+        let arrow_style = ArrowStyle {
+            line: LineStyle::Dashed,
+            head: HeadStyle::Open,
+            activation: None,
+        };
+        let document = vec![Line {
+            line_contents: LineContents::Interaction(
+                FromParticipant("B".to_owned()),
+                ToParticipant("A".to_owned()),
+                arrow_style,
+            ),
+            line_data: "B -->> A".to_owned(),
+            line_number: 0,
+        }];
+
+        let participants = make_participants(&["A", "B"]);
+
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
+        assert_eq!(arrow_style, inters[0].arrow_style);
+    }
+
     #[test]
     fn test_interaction_parser_skips_non_interaction_lines() {
         let document = vec![
@@ -218,17 +254,36 @@ mod tests {
                 line_contents: LineContents::Interaction(
                     FromParticipant("A".to_owned()),
                     ToParticipant("B".to_owned()),
+                    ArrowStyle::default(),
                 ),
                 line_data: "A -> B".to_owned(),
                 line_number: 2,
             },
         ];
 
-        let mut participants = HashSet::new();
-        participants.insert(make_participant("A", 0));
-        participants.insert(make_participant("B", 1));
+        let participants = make_participants(&["A", "B"]);
 
-        let inters = InteractionParser::parse(&document, &participants);
+        let inters = InteractionParser::parse(&document, &participants).unwrap();
         assert_eq!(1, inters.len());
     }
+
+    #[test]
+    fn test_interaction_parser_unknown_participant() {
+        let document = vec![Line {
+            line_contents: LineContents::Interaction(
+                FromParticipant("A".to_owned()),
+                ToParticipant("Missing".to_owned()),
+                ArrowStyle::default(),
+            ),
+            line_data: "A -> Missing".to_owned(),
+            line_number: 0,
+        }];
+
+        let participants = make_participants(&["A"]);
+
+        match InteractionParser::parse(&document, &participants) {
+            Err(Error::UnknownParticipant(name)) => assert_eq!("Missing", name),
+            other => panic!("expected unknown participant, got {:?}", other),
+        }
+    }
 }
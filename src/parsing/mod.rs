@@ -1,3 +1,8 @@
+pub mod activation;
+pub mod autonumber;
 pub mod document;
+pub mod fragment;
+pub mod header;
 pub mod interaction;
+pub mod note;
 pub mod participant;
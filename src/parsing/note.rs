@@ -0,0 +1,106 @@
+use log::info;
+
+use crate::error::{Error, Result};
+use crate::model::{Line, LineContents, Note, NoteLine, Participants};
+
+// == Note Parser =========================================
+#[derive(Debug, Default)]
+pub struct NoteParser;
+
+impl NoteParser {
+    /// Resolve the participants of each `Note` line into a [`Note`], keeping
+    /// the line number so notes can be ordered among interactions.
+    pub fn parse(document: &[Line], participants: &Participants) -> Result<Vec<Note>> {
+        let notes = document
+            .iter()
+            .filter_map(|line| match &line.line_contents {
+                LineContents::Note(note) => {
+                    Some(Self::parse_note(line.line_number, note, participants))
+                }
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!("Notes: {:?}", notes);
+        Ok(notes)
+    }
+
+    fn parse_note(
+        line_number: usize,
+        note: &NoteLine,
+        participants: &Participants,
+    ) -> Result<Note> {
+        let ids = note
+            .participants
+            .iter()
+            .map(|name| {
+                participants
+                    .id(name)
+                    .ok_or_else(|| Error::UnknownParticipant(name.to_owned()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let first = *ids.iter().min().ok_or(Error::EmptyNote(line_number))?;
+        let last = *ids.iter().max().ok_or(Error::EmptyNote(line_number))?;
+
+        Ok(Note {
+            line_number,
+            placement: note.placement,
+            participants: (first, last),
+            text: note.text.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{NotePlacement, ParticipantId};
+
+    fn make_line(line_number: usize, placement: NotePlacement, names: &[&str]) -> Line {
+        Line {
+            line_contents: LineContents::Note(NoteLine {
+                placement,
+                participants: names.iter().map(|n| n.to_string()).collect(),
+                text: vec!["text".to_string()],
+            }),
+            line_data: String::new(),
+            line_number,
+        }
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let mut participants = Participants::default();
+        participants.insert("A", 0);
+        participants.insert("B", 0);
+        let document = vec![
+            make_line(3, NotePlacement::LeftOf, &["A"]),
+            make_line(5, NotePlacement::Over, &["B", "A"]),
+        ];
+
+        let notes = NoteParser::parse(&document, &participants).unwrap();
+        assert_eq!(2, notes.len());
+        assert_eq!(3, notes[0].line_number);
+        assert_eq!((ParticipantId(0), ParticipantId(0)), notes[0].participants);
+        assert_eq!(NotePlacement::Over, notes[1].placement);
+        assert_eq!((ParticipantId(0), ParticipantId(1)), notes[1].participants);
+    }
+
+    #[test]
+    fn test_parse_note_unknown_participant() {
+        let document = vec![make_line(0, NotePlacement::RightOf, &["C"])];
+        assert!(matches!(
+            NoteParser::parse(&document, &Participants::default()),
+            Err(Error::UnknownParticipant(name)) if name == "C"
+        ));
+    }
+
+    #[test]
+    fn test_parse_note_without_participants() {
+        let document = vec![make_line(4, NotePlacement::Over, &[])];
+        assert!(matches!(
+            NoteParser::parse(&document, &Participants::default()),
+            Err(Error::EmptyNote(4))
+        ));
+    }
+}
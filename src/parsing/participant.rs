@@ -1,109 +1,72 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 use log::info;
 
-use crate::model::{Line, LineContents, Participant};
-use crate::rendering::text::measure_string;
-use crate::rendering::Rect;
-use crate::theme::Theme;
-use crate::ParticipantSet;
+use crate::model::{Line, LineContents, Participants};
 
 // == Participant Parser ==================================
 #[derive(Debug, Default)]
 pub struct ParticipantParser;
 
 impl ParticipantParser {
-    /// Iterate lines, noting the first and last appearance of each participant
-    /// to compute their index, active range, and on-screen rect.
-    pub fn parse(document: &[Line], theme: &Theme) -> ParticipantSet {
-        let mut current_participant_index: usize = 0;
-        let mut current_interaction_index: usize = 0;
-        let mut participant_indices: HashMap<String, usize> = HashMap::new();
-        let mut first_index_for_participant: HashMap<String, usize> = HashMap::new();
-        let mut last_index_for_participant: HashMap<String, usize> = HashMap::new();
-        let mut x_position_for_participant: HashMap<String, usize> = HashMap::new();
-        let mut rect_for_participant: HashMap<String, Rect> = HashMap::new();
-
-        let mut current_x = theme.document_border_width;
-        let partic_h_gap = theme.partic_h_gap;
-
-        document
-            .iter()
-            .filter(|line| {
-                matches!(
-                    line.line_contents,
-                    LineContents::Interaction(_, _) | LineContents::InteractionWithMessage(_, _, _)
-                )
-            })
-            .for_each(|line| {
-                info!("Pass 1: {:#?}", line);
-
-                let (f, t) = match &line.line_contents {
-                    LineContents::Interaction(f, t) => (f, t),
-                    LineContents::InteractionWithMessage(f, t, _) => (f, t),
-                    _ => unreachable!("filter above only allows Interaction variants"),
-                };
+    /// Declared participants take the first columns in declaration order,
+    /// then iterate interactions, noting the first and last appearance of each
+    /// participant to compute their column and active range. Participants
+    /// only named by notes, activations or `create` and `destroy` lines get a
+    /// column but no active range; the latter two also mark the lifeline.
+    pub fn parse(document: &[Line]) -> Participants {
+        let mut participants = Participants::default();
+        document.iter().for_each(|line| {
+            if let LineContents::Participant(declaration) = &line.line_contents {
+                participants.declare(declaration);
+            }
+        });
 
+        let mut seen = HashSet::new();
+        let mut interaction_index = 0;
+        document.iter().for_each(|line| match &line.line_contents {
+            LineContents::Interaction(f, t, _)
+            | LineContents::InteractionWithMessage(f, t, _, _) => {
                 for participant_name in [&f.0, &t.0] {
-                    if !participant_indices.contains_key(participant_name) {
-                        participant_indices
-                            .insert(participant_name.to_string(), current_participant_index);
-                        current_participant_index += 1;
-
-                        x_position_for_participant
-                            .insert(participant_name.to_string(), current_x);
-                        let string_rect =
-                            measure_string(theme, participant_name, theme.partic_font_px);
-                        rect_for_participant
-                            .insert(participant_name.to_string(), string_rect);
-                        current_x += partic_h_gap + string_rect.w;
+                    let id = participants.insert(participant_name, interaction_index);
+                    if seen.insert(id) {
+                        participants[id].active_from = interaction_index;
                     }
-
-                    first_index_for_participant
-                        .entry(participant_name.to_string())
-                        .or_insert(current_interaction_index);
-
-                    last_index_for_participant
-                        .insert(participant_name.to_string(), current_interaction_index);
+                    participants[id].active_to = interaction_index;
                 }
+                interaction_index += 1;
+            }
+            LineContents::Note(note) => {
+                for participant_name in &note.participants {
+                    participants.insert(participant_name, interaction_index);
+                }
+            }
+            LineContents::Activation(_, participant_name) => {
+                participants.insert(participant_name, interaction_index);
+            }
+            LineContents::Create(participant_name) => {
+                let id = participants.insert(participant_name, interaction_index);
+                participants[id].created = true;
+            }
+            LineContents::Destroy(participant_name) => {
+                let id = participants.insert(participant_name, interaction_index);
+                participants[id].destroyed = Some(line.line_number);
+            }
+            _ => {}
+        });
 
-                current_interaction_index += 1;
-            });
-
-        info!("After first pass:");
-        info!("Participant idx: {:#?}", participant_indices);
-        info!("Participant active from: {:#?}", first_index_for_participant);
-        info!("Participant active to: {:#?}", last_index_for_participant);
-
-        let max_height = rect_for_participant.values().map(|r| r.h).max().unwrap_or(0);
-        let partic_y = theme.document_border_width;
-
-        participant_indices
-            .iter()
-            .map(|(name, &index)| Participant {
-                active_from: *first_index_for_participant.get(name).unwrap(),
-                active_to: *last_index_for_participant.get(name).unwrap(),
-                rect: Rect {
-                    x: *x_position_for_participant.get(name).unwrap(),
-                    y: partic_y,
-                    w: rect_for_participant.get(name).unwrap().w,
-                    h: max_height,
-                },
-                name: name.clone(),
-                index,
-            })
-            .collect()
+        info!("Participants: {:#?}", participants);
+        participants
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{FromParticipant, InteractionMessage, ToParticipant};
-
-    fn make_theme() -> Theme {
-        Theme::default()
-    }
+    use crate::model::{
+        ArrowStyle, FromParticipant, InteractionMessage, ParticipantDeclaration, ParticipantId,
+        ParticipantKind, ToParticipant,
+    };
 
     #[test]
     fn test_parse_participant_names() {
@@ -111,15 +74,20 @@ mod tests {
             line_contents: LineContents::InteractionWithMessage(
                 FromParticipant("Client".to_string()),
                 ToParticipant("Server".to_string()),
+                ArrowStyle::default(),
                 InteractionMessage("Message".to_string()),
             ),
             line_data: "Client -> Server: Message".to_string(),
             line_number: 0,
         }];
-        let data = ParticipantParser::parse(&document, &make_theme());
+        let data = ParticipantParser::parse(&document);
         assert_eq!(2, data.len());
-        assert_eq!(0, data.iter().find(|p| p.name == "Client").unwrap().index);
-        assert_eq!(1, data.iter().find(|p| p.name == "Server").unwrap().index);
+        assert_eq!(Some(ParticipantId(0)), data.id("Client"));
+        assert_eq!(Some(ParticipantId(1)), data.id("Server"));
+        assert_eq!(
+            vec!["Client", "Server"],
+            data.iter().map(|p| p.name.as_str()).collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -134,6 +102,7 @@ mod tests {
                 line_contents: LineContents::InteractionWithMessage(
                     FromParticipant("Client".to_string()),
                     ToParticipant("Server".to_string()),
+                    ArrowStyle::default(),
                     InteractionMessage("Message".to_string()),
                 ),
                 line_data: "Client -> Server: Message".to_string(),
@@ -143,6 +112,7 @@ mod tests {
                 line_contents: LineContents::InteractionWithMessage(
                     FromParticipant("Server".to_string()),
                     ToParticipant("Database".to_string()),
+                    ArrowStyle::default(),
                     InteractionMessage("Query".to_string()),
                 ),
                 line_data: "Server -> Database: Query".to_string(),
@@ -152,27 +122,28 @@ mod tests {
                 line_contents: LineContents::Interaction(
                     FromParticipant("Server".to_string()),
                     ToParticipant("Client".to_string()),
+                    ArrowStyle::default(),
                 ),
                 line_data: "Server -> Client".to_string(),
                 line_number: 3,
             },
         ];
-        let data = ParticipantParser::parse(&document, &make_theme());
+        let data = ParticipantParser::parse(&document);
         assert_eq!(3, data.len());
 
-        let client = data.iter().find(|p| p.name == "Client").unwrap();
-        let server = data.iter().find(|p| p.name == "Server").unwrap();
-        let database = data.iter().find(|p| p.name == "Database").unwrap();
+        let client = &data[data.id("Client").unwrap()];
+        let server = &data[data.id("Server").unwrap()];
+        let database = &data[data.id("Database").unwrap()];
 
-        assert_eq!(0, client.index);
+        assert_eq!(ParticipantId(0), client.id);
         assert_eq!(0, client.active_from);
         assert_eq!(2, client.active_to);
 
-        assert_eq!(1, server.index);
+        assert_eq!(ParticipantId(1), server.id);
         assert_eq!(0, server.active_from);
         assert_eq!(2, server.active_to);
 
-        assert_eq!(2, database.index);
+        assert_eq!(ParticipantId(2), database.id);
         assert_eq!(1, database.active_from);
         assert_eq!(1, database.active_to);
     }
@@ -189,31 +160,107 @@ mod tests {
                 line_contents: LineContents::Interaction(
                     FromParticipant("A".to_string()),
                     ToParticipant("B".to_string()),
+                    ArrowStyle::default(),
                 ),
                 line_data: "A -> B".to_string(),
                 line_number: 1,
             },
         ];
-        let data = ParticipantParser::parse(&document, &make_theme());
+        let data = ParticipantParser::parse(&document);
         assert_eq!(2, data.len());
     }
 
     #[test]
-    fn test_parse_participants_rect_has_max_height() {
-        // All participants should share the max height from measure_string
+    fn test_parse_declarations_fix_column_order() {
         let document = vec![
+            Line {
+                line_contents: LineContents::Participant(ParticipantDeclaration {
+                    kind: ParticipantKind::Database,
+                    display_name: "Database".to_string(),
+                    alias: None,
+                }),
+                line_data: "database Database".to_string(),
+                line_number: 0,
+            },
+            Line {
+                line_contents: LineContents::Participant(ParticipantDeclaration {
+                    kind: ParticipantKind::Participant,
+                    display_name: "Order Service".to_string(),
+                    alias: Some("OS".to_string()),
+                }),
+                line_data: "participant \"Order Service\" as OS".to_string(),
+                line_number: 1,
+            },
             Line {
                 line_contents: LineContents::Interaction(
-                    FromParticipant("A".to_string()),
-                    ToParticipant("LongName".to_string()),
+                    FromParticipant("Client".to_string()),
+                    ToParticipant("OS".to_string()),
+                    ArrowStyle::default(),
                 ),
-                line_data: "A -> LongName".to_string(),
-                line_number: 0,
+                line_data: "Client -> OS".to_string(),
+                line_number: 2,
+            },
+            Line {
+                line_contents: LineContents::Interaction(
+                    FromParticipant("OS".to_string()),
+                    ToParticipant("Database".to_string()),
+                    ArrowStyle::default(),
+                ),
+                line_data: "OS -> Database".to_string(),
+                line_number: 3,
             },
         ];
-        let data = ParticipantParser::parse(&document, &make_theme());
-        let heights: Vec<usize> = data.iter().map(|p| p.rect.h).collect();
-        let first = heights[0];
-        assert!(heights.iter().all(|&h| h == first), "all participants should share max height");
+        let data = ParticipantParser::parse(&document);
+        assert_eq!(
+            vec!["Database", "OS", "Client"],
+            data.iter().map(|p| p.name.as_str()).collect::<Vec<_>>()
+        );
+
+        let service = &data[ParticipantId(1)];
+        assert_eq!("Order Service", service.display_name);
+        assert_eq!(Some(service.id), data.id("Order Service"));
+        assert_eq!(0, service.active_from);
+        assert_eq!(1, service.active_to);
+
+        let database = &data[ParticipantId(0)];
+        assert_eq!(ParticipantKind::Database, database.kind);
+        assert_eq!(ParticipantKind::Participant, data[ParticipantId(2)].kind);
+        assert_eq!(1, database.active_from);
+        assert_eq!(1, database.active_to);
+    }
+
+    #[test]
+    fn test_parse_created_and_destroyed_participants() {
+        let interaction = |from: &str, to: &str| {
+            LineContents::Interaction(
+                FromParticipant(from.to_string()),
+                ToParticipant(to.to_string()),
+                ArrowStyle::default(),
+            )
+        };
+        let document = [
+            interaction("A", "B"),
+            LineContents::Create("Worker".to_string()),
+            interaction("B", "Worker"),
+            interaction("Worker", "B"),
+            LineContents::Destroy("Worker".to_string()),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(line_number, line_contents)| Line {
+            line_contents,
+            line_data: String::new(),
+            line_number,
+        })
+        .collect::<Vec<_>>();
+
+        let data = ParticipantParser::parse(&document);
+        let worker = &data[data.id("Worker").unwrap()];
+        assert_eq!(ParticipantId(2), worker.id);
+        assert!(worker.created);
+        assert_eq!(Some(4), worker.destroyed);
+        assert_eq!((1, 2), (worker.active_from, worker.active_to));
+        assert!(!data[ParticipantId(0)].created);
+        assert_eq!(None, data[ParticipantId(0)].destroyed);
     }
 }
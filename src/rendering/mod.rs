@@ -1,10 +1,18 @@
-use itertools::Itertools;
+use std::io::Write;
+
 use log::info;
 use raqote::{DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle};
 
-use super::{diagram::Diagram, model::Participant, theme::Theme, ParticipantSet};
-use crate::rendering::text::draw_text;
+use super::{
+    diagram::Diagram,
+    error::{Error, Result},
+    layout::scene::{Element, Point, Scene, Stroke},
+    model::OutputFormat,
+    theme::{Colour, Theme},
+};
+use crate::rendering::text::{draw_text, GlyphCache};
 
+pub mod svg;
 pub mod text;
 
 pub trait RenderSet {
@@ -24,106 +32,237 @@ pub trait Render {
 }
 
 impl Diagram {
-    pub fn render(&self) {
-        let size = self.size(&self.theme);
-        let mut rendering_context = RenderContext::new(size, self.theme.clone());
-
-        self.participants.render(&mut rendering_context);
+    /// Renders the diagram in the given format and returns the encoded image.
+    pub fn render(&self, format: OutputFormat) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.render_to(format, &mut buffer)?;
+        Ok(buffer)
+    }
 
-        rendering_context
-            .draw_target
-            .write_png(&self.config.output_path)
-            .unwrap();
-        info!("Wrote file...");
+    /// Renders the diagram in the given format, writing the encoded image to
+    /// `writer`.
+    pub fn render_to<W: Write>(&self, format: OutputFormat, mut writer: W) -> Result<()> {
+        let scene = self.layout();
+        match format {
+            OutputFormat::Png => {
+                let mut rendering_context = RenderContext::new(&scene, self.theme.clone());
+                scene.elements.render(&mut rendering_context);
+                encode_png(&rendering_context.draw_target, writer)?;
+            }
+            OutputFormat::Svg => {
+                writer.write_all(scene.render_svg(&self.theme).as_bytes())?;
+            }
+        }
+        info!("Rendered {:?}...", format);
+        Ok(())
     }
 }
 
-impl RenderSet for ParticipantSet {
+/// Encodes the draw target as an RGBA PNG, un-premultiplying each pixel the
+/// same way `DrawTarget::write_png` does.
+fn encode_png<W: Write>(draw_target: &DrawTarget, writer: W) -> Result<()> {
+    let mut encoder = png::Encoder::new(
+        writer,
+        draw_target.width() as u32,
+        draw_target.height() as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data = draw_target
+        .get_data()
+        .iter()
+        .flat_map(|pixel| {
+            let a = (pixel >> 24) & 0xff;
+            let [r, g, b] = [16, 8, 0].map(|shift| {
+                let c = (pixel >> shift) & 0xff;
+                (c * 255).checked_div(a).unwrap_or(c)
+            });
+            [r as u8, g as u8, b as u8, a as u8]
+        })
+        .collect::<Vec<_>>();
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| match e {
+            png::EncodingError::IoError(e) => Error::Io(e),
+            e => Error::Encoding(e.to_string()),
+        })
+}
+
+impl RenderSet for Vec<Element> {
     fn render(&self, context: &mut RenderContext) {
-        self.iter().sorted_by_key(|k| k.index).for_each(|p| {
-            p.render(context);
-        });
+        self.iter().for_each(|e| e.render(context));
     }
 }
 
-impl Render for Participant {
+impl Render for Element {
     fn render(&self, context: &mut RenderContext) {
-        let participant_padding = context.theme.partic_padding;
-
-        let mut path = PathBuilder::new();
-        path.rect(
-            (self.rect.x + participant_padding) as f32,
-            (self.rect.y + participant_padding) as f32,
-            (self.rect.w + (participant_padding * 2)) as f32,
-            (self.rect.h + (participant_padding * 2)) as f32,
-        );
-
-        let ss = StrokeStyle {
-            width: 0.5,
-            ..StrokeStyle::default()
-        };
+        match self {
+            Element::Box {
+                x,
+                y,
+                w,
+                h,
+                fill,
+                stroke,
+            } => {
+                let mut path = PathBuilder::new();
+                path.rect(*x, *y, *w, *h);
+                fill_and_stroke(context, &path.finish(), *fill, stroke.as_ref());
+            }
+            Element::Line { points, stroke } => {
+                context.draw_target.stroke(
+                    &polyline(points, false),
+                    &Source::Solid(solid_source(stroke.colour)),
+                    &stroke_style(stroke),
+                    &DrawOptions::default(),
+                );
+            }
+            Element::Polygon { points, fill } => {
+                context.draw_target.fill(
+                    &polyline(points, true),
+                    &Source::Solid(solid_source(*fill)),
+                    &DrawOptions::default(),
+                );
+            }
+            Element::Ellipse {
+                cx,
+                cy,
+                rx,
+                ry,
+                fill,
+                stroke,
+            } => {
+                fill_and_stroke(
+                    context,
+                    &ellipse(*cx, *cy, *rx, *ry),
+                    *fill,
+                    stroke.as_ref(),
+                );
+            }
+            Element::Text(text) => {
+                let (x, y) = (text.x as usize, text.y as usize);
+                draw_text(
+                    context,
+                    text.font,
+                    &text.content,
+                    x,
+                    y,
+                    text.px,
+                    text.colour,
+                );
+            }
+        }
+    }
+}
 
-        context.draw_target.stroke(
-            &path.finish(),
-            &Source::Solid(SolidSource::from_unpremultiplied_argb(225, 255, 20, 20)),
-            &ss,
+fn fill_and_stroke(
+    context: &mut RenderContext,
+    path: &raqote::Path,
+    fill: Option<Colour>,
+    stroke: Option<&Stroke>,
+) {
+    if let Some(fill) = fill {
+        context.draw_target.fill(
+            path,
+            &Source::Solid(solid_source(fill)),
             &DrawOptions::default(),
         );
-
-        draw_text(
-            context,
-            &self.name,
-            self.rect.x + (2 * participant_padding),
-            self.rect.y + participant_padding,
-            context.theme.partic_font_px,
-        );
-
-        info!(
-            "Drawing box for {} x: {}, y: {}, w: {}, h: {}",
-            self.name, self.rect.x, self.rect.y, self.rect.w, self.rect.h
+    }
+    if let Some(stroke) = stroke {
+        context.draw_target.stroke(
+            path,
+            &Source::Solid(solid_source(stroke.colour)),
+            &stroke_style(stroke),
+            &DrawOptions::default(),
         );
     }
 }
 
-impl Sizable for Diagram {
-    fn size(&self, theme: &Theme) -> Size {
-        let interaction_height = self.interactions.iter().map(|p| p.index).max().unwrap_or(0);
-        let height: i32 =
-            ((2 * theme.document_border_width) + (interaction_height as usize * 20)) as i32;
+/// An ellipse approximated by four cubic Béziers, one per quadrant.
+fn ellipse(cx: f32, cy: f32, rx: f32, ry: f32) -> raqote::Path {
+    // distance of the control points along the tangent, relative to the radius
+    const KAPPA: f32 = 0.552_284_8;
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let mut path = PathBuilder::new();
+    path.move_to(cx + rx, cy);
+    path.cubic_to(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry);
+    path.cubic_to(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy);
+    path.cubic_to(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry);
+    path.cubic_to(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy);
+    path.close();
+    path.finish()
+}
 
-        let max_x_participant = self
-            .participants
-            .iter()
-            .max_by_key(|p| p.rect.x)
-            .unwrap();
-        let width: i32 = (max_x_participant.rect.x
-            + max_x_participant.rect.w
-            + (2 * self.theme.partic_padding)
-            + (2 * theme.document_border_width)) as i32;
+fn polyline(points: &[Point], close: bool) -> raqote::Path {
+    let mut path = PathBuilder::new();
+    if let Some((first, rest)) = points.split_first() {
+        path.move_to(first.x, first.y);
+        rest.iter().for_each(|p| path.line_to(p.x, p.y));
+    }
+    if close {
+        path.close();
+    }
+    path.finish()
+}
 
-        Size { height, width }
+fn stroke_style(stroke: &Stroke) -> StrokeStyle {
+    StrokeStyle {
+        width: stroke.width,
+        dash_array: stroke.dash.clone(),
+        ..StrokeStyle::default()
     }
 }
 
+fn solid_source(colour: Colour) -> SolidSource {
+    SolidSource::from_unpremultiplied_argb(colour.a, colour.r, colour.g, colour.b)
+}
+
 pub struct RenderContext {
     pub theme: Theme,
     pub draw_target: DrawTarget,
+    pub glyph_cache: GlyphCache,
 }
 
 impl RenderContext {
-    fn new(size: Size, theme: Theme) -> Self {
-        let mut draw_target = DrawTarget::new(size.width, size.height);
-        draw_target.clear(SolidSource::from_unpremultiplied_argb(255, 255, 255, 255));
-        RenderContext { theme, draw_target }
+    /// A context with a draw target sized to the scene and cleared to its
+    /// background.
+    pub fn new(scene: &Scene, theme: Theme) -> Self {
+        let mut draw_target = DrawTarget::new(scene.width as i32, scene.height as i32);
+        draw_target.clear(solid_source(scene.background));
+        RenderContext {
+            theme,
+            draw_target,
+            glyph_cache: GlyphCache::default(),
+        }
     }
 }
 
-pub trait Sizable {
-    fn size(&self, theme: &Theme) -> Size;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::document::parse_example;
+
+    fn diagram(text: &str) -> Diagram {
+        Diagram::parse(parse_example(text), Theme::default()).unwrap()
+    }
 
-#[derive(Debug)]
-pub struct Size {
-    pub height: i32,
-    pub width: i32,
+    #[test]
+    fn test_render_png_to_buffer() {
+        let png = diagram("Client -> Server: Request")
+            .render(OutputFormat::Png)
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn test_render_to_writer() {
+        let diagram = diagram("Client -> Server: Request");
+        let mut svg = Vec::new();
+        diagram.render_to(OutputFormat::Svg, &mut svg).unwrap();
+        assert_eq!(diagram.render(OutputFormat::Svg).unwrap(), svg);
+        assert!(String::from_utf8(svg).unwrap().starts_with("<svg "));
+    }
 }
@@ -0,0 +1,217 @@
+use std::fmt::Write;
+
+use log::info;
+
+use crate::diagram::Diagram;
+use crate::layout::scene::{Element, Point, Scene, Stroke, TextRun};
+use crate::theme::{Colour, Theme};
+
+impl Diagram {
+    /// Renders the diagram as an SVG document.
+    pub fn render_svg(&self) -> String {
+        self.layout().render_svg(&self.theme)
+    }
+}
+
+impl Scene {
+    /// Paints the scene as an SVG document; text is emitted as `<text>`
+    /// elements so it stays selectable and searchable.
+    pub fn render_svg(&self, theme: &Theme) -> String {
+        let mut body = String::new();
+        self.elements
+            .iter()
+            .for_each(|e| write_element(&mut body, e, theme));
+
+        info!("Rendered svg...");
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"{background}\"/>\n{body}</svg>\n",
+            w = self.width,
+            h = self.height,
+            background = colour(self.background),
+            body = body
+        )
+    }
+}
+
+fn write_element(body: &mut String, element: &Element, theme: &Theme) {
+    let _ = match element {
+        Element::Box {
+            x,
+            y,
+            w,
+            h,
+            fill,
+            stroke,
+        } => writeln!(
+            body,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{}/>",
+            x,
+            y,
+            w,
+            h,
+            fill_attributes(*fill),
+            stroke.as_ref().map(stroke_attributes).unwrap_or_default()
+        ),
+        Element::Line { points, stroke } => writeln!(
+            body,
+            "<polyline points=\"{}\" fill=\"none\"{}/>",
+            points_attribute(points),
+            stroke_attributes(stroke)
+        ),
+        Element::Polygon { points, fill } => writeln!(
+            body,
+            "<polygon points=\"{}\"{}/>",
+            points_attribute(points),
+            fill_attributes(Some(*fill))
+        ),
+        Element::Ellipse {
+            cx,
+            cy,
+            rx,
+            ry,
+            fill,
+            stroke,
+        } => writeln!(
+            body,
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{}{}/>",
+            cx,
+            cy,
+            rx,
+            ry,
+            fill_attributes(*fill),
+            stroke.as_ref().map(stroke_attributes).unwrap_or_default()
+        ),
+        Element::Text(text) => write_text(body, text, theme),
+    };
+}
+
+/// Writes a `<text>` element whose top edge sits at the run's `y`, matching
+/// the positioning used by `draw_text`.
+fn write_text(body: &mut String, text: &TextRun, theme: &Theme) -> std::fmt::Result {
+    let ascent = theme
+        .font(text.font)
+        .horizontal_line_metrics(text.px as f32)
+        .map(|m| m.ascent)
+        .unwrap_or(text.px as f32);
+    writeln!(
+        body,
+        concat!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-weight=\"{}\"",
+            " font-size=\"{}\"{}>{}</text>"
+        ),
+        text.x,
+        text.y + ascent,
+        escape(theme.font_family(text.font)),
+        theme.font_weight(text.font),
+        text.px,
+        fill_attributes(Some(text.colour)),
+        escape(&text.content)
+    )
+}
+
+fn points_attribute(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn fill_attributes(fill: Option<Colour>) -> String {
+    match fill {
+        Some(fill) => format!(" fill=\"{}\"{}", colour(fill), opacity("fill", fill)),
+        None => " fill=\"none\"".to_string(),
+    }
+}
+
+fn stroke_attributes(stroke: &Stroke) -> String {
+    let dash = if stroke.dash.is_empty() {
+        String::new()
+    } else {
+        let dash = stroke
+            .dash
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        format!(" stroke-dasharray=\"{}\"", dash.join(" "))
+    };
+    format!(
+        " stroke=\"{}\"{} stroke-width=\"{}\"{}",
+        colour(stroke.colour),
+        opacity("stroke", stroke.colour),
+        stroke.width,
+        dash
+    )
+}
+
+fn colour(colour: Colour) -> String {
+    format!("rgb({},{},{})", colour.r, colour.g, colour.b)
+}
+
+fn opacity(attribute: &str, colour: Colour) -> String {
+    if colour.a == 255 {
+        String::new()
+    } else {
+        format!(" {}-opacity=\"{:.2}\"", attribute, colour.a as f32 / 255.0)
+    }
+}
+
+/// Escapes the characters that are special in SVG text and attributes.
+fn escape(content: &str) -> String {
+    content
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::document::parse_example;
+
+    fn render(text: &str) -> String {
+        Diagram::parse(parse_example(text), Theme::default())
+            .unwrap()
+            .render_svg()
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("a &lt;b&gt; &amp; &quot;c&quot;", escape("a <b> & \"c\""));
+    }
+
+    #[test]
+    fn test_render_svg_emits_text_elements() {
+        let svg = render(":title Flow\nClient -> Server: Request <1>\nServer --> Client");
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">Flow</text>"));
+        assert!(svg.contains(">Client</text>"));
+        assert!(svg.contains(">Server</text>"));
+        assert!(svg.contains(">Request &lt;1&gt;</text>"));
+        assert!(svg.contains("stroke-dasharray=\"6 4\""));
+    }
+
+    #[test]
+    fn test_render_svg_emits_ellipses() {
+        let svg = render("database DB\nClient -> DB");
+        assert_eq!(2, svg.matches("<ellipse ").count());
+    }
+
+    #[test]
+    fn test_render_svg_text_uses_role_fonts() {
+        let svg = render(":title Flow\nClient -> Server: Request");
+        let text = |content: &str| {
+            svg.lines()
+                .find(|line| line.ends_with(&format!(">{}</text>", content)))
+                .unwrap()
+                .to_owned()
+        };
+        assert!(text("Flow").contains("font-family=\"Roboto, sans-serif\" font-weight=\"900\""));
+        assert!(text("Client").contains("font-family=\"Roboto, sans-serif\" font-weight=\"100\""));
+        assert!(
+            text("Request").contains("font-family=\"'Open Sans', sans-serif\" font-weight=\"400\"")
+        );
+    }
+}
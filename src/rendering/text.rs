@@ -1,52 +1,63 @@
+use std::collections::HashMap;
+
 use super::{Rect, RenderContext};
-use crate::theme::Theme;
+use crate::theme::{Colour, FontRole, Theme};
 
-use fontdue::layout::{CoordinateSystem, Layout, LayoutSettings, TextStyle};
+use fontdue::layout::{CoordinateSystem, GlyphPosition, Layout, LayoutSettings, TextStyle};
+use fontdue::Font;
+use raqote::{BlendMode, DrawTarget, IntPoint, IntRect};
 
 #[cfg(debug_assertions)]
 use raqote::{DrawOptions, PathBuilder, SolidSource, Source, StrokeStyle};
 
-/// Measures the bounding box of the rendered string at the given font size.
-pub fn measure_string(theme: &Theme, content: &str, px: usize) -> Rect {
+/// Measures the bounding box of the string rendered in the font for `role`.
+pub fn measure_string(theme: &Theme, role: FontRole, content: &str, px: usize) -> Rect {
     debug_assert!(!content.is_empty());
     debug_assert!(px > 0);
 
-    let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
-    layout.reset(&LayoutSettings {
-        x: 0.0,
-        y: 0.0,
-        ..LayoutSettings::default()
-    });
-    let font = &theme.body_font;
-    layout.append(&[font], &TextStyle::new(content, px as f32, 0));
-
+    let layout = layout_text(&theme.font_chain(role), content, 0, 0, px);
     let glyphs = layout.glyphs();
-    let first_glyph = glyphs.first().unwrap();
-    let last_glyph = glyphs.last().unwrap();
+    let first_glyph = match glyphs.first() {
+        Some(glyph) => glyph,
+        None => {
+            return Rect {
+                x: 0,
+                y: 0,
+                w: 0,
+                h: 0,
+            }
+        }
+    };
     let y = glyphs.iter().map(|g| g.y as usize).min().unwrap();
     let h = glyphs.iter().map(|g| g.height).max().unwrap();
+    // the right-most inked column, measured from the layout origin
+    let w = glyphs.iter().map(|g| g.x as usize + g.width).max().unwrap();
 
     Rect {
         x: first_glyph.x as usize,
         y,
-        w: (first_glyph.x as usize + last_glyph.x as usize + last_glyph.width),
+        w,
         h,
     }
 }
 
-/// Draws text into the render context at the given position and font size.
-pub fn draw_text(rc: &mut RenderContext, content: &str, x: usize, y: usize, px: usize) {
-    let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
-    layout.reset(&LayoutSettings {
-        x: x as f32,
-        y: y as f32,
-        ..LayoutSettings::default()
-    });
-    let font = &rc.theme.body_font;
-    layout.append(&[font], &TextStyle::new(content, px as f32, 0));
+/// Draws text in the font for `role` into the render context, with the top
+/// left of its line box at the given position.
+pub fn draw_text(
+    rc: &mut RenderContext,
+    role: FontRole,
+    content: &str,
+    x: usize,
+    y: usize,
+    px: usize,
+    colour: Colour,
+) {
+    let fonts = rc.theme.font_chain(role);
+    let layout = layout_text(&fonts, content, x, y, px);
 
     for glyph in layout.glyphs() {
-        let (metrics, coverage) = font.rasterize(glyph.parent, px as f32);
+        let font = fonts[glyph.font_index];
+        let image = rc.glyph_cache.get(font, glyph, px, colour);
         log::info!("Metrics: {:?}", glyph);
 
         #[cfg(debug_assertions)]
@@ -55,8 +66,8 @@ pub fn draw_text(rc: &mut RenderContext, content: &str, x: usize, y: usize, px:
             path.rect(
                 glyph.x,
                 glyph.y,
-                metrics.width as f32,
-                metrics.height as f32,
+                image.surface.width() as f32,
+                image.surface.height() as f32,
             );
             rc.draw_target.stroke(
                 &path.finish(),
@@ -66,22 +77,143 @@ pub fn draw_text(rc: &mut RenderContext, content: &str, x: usize, y: usize, px:
             );
         }
 
-        let image_data: Vec<u32> = coverage
+        // glyphs are blended at whole pixel positions, matching the
+        // truncation in `measure_string`
+        let size = image.surface.width() * image.surface.height();
+        if size > 0 {
+            rc.draw_target.blend_surface(
+                &image.surface,
+                IntRect::new(
+                    IntPoint::origin(),
+                    IntPoint::new(image.surface.width(), image.surface.height()),
+                ),
+                IntPoint::new(glyph.x as i32, glyph.y as i32),
+                BlendMode::SrcOver,
+            );
+        }
+    }
+}
+
+// == Glyph Cache =========================================
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font_hash: usize,
+    glyph_index: u16,
+    px: usize,
+    /// Images are premultiplied, so each colour is cached separately.
+    colour: Colour,
+}
+
+/// A rasterized glyph, premultiplied and ready to blend.
+pub struct GlyphImage {
+    pub surface: DrawTarget,
+}
+
+/// Rasterized glyph images keyed by font, glyph, pixel size and colour, so
+/// that repeated characters are only rasterized once per render.
+#[derive(Default)]
+pub struct GlyphCache {
+    glyphs: HashMap<GlyphKey, GlyphImage>,
+}
+
+impl GlyphCache {
+    /// The image for a laid out glyph, rasterizing it on first use.
+    pub fn get(
+        &mut self,
+        font: &Font,
+        glyph: &GlyphPosition,
+        px: usize,
+        colour: Colour,
+    ) -> &GlyphImage {
+        let key = GlyphKey {
+            font_hash: font.file_hash(),
+            glyph_index: glyph.key.glyph_index,
+            px,
+            colour,
+        };
+        self.glyphs.entry(key).or_insert_with(|| {
+            let (metrics, coverage) = font.rasterize_indexed(key.glyph_index, px as f32);
+            let data = coverage
+                .iter()
+                .map(|&cov| glyph_pixel(colour, cov))
+                .collect();
+            GlyphImage {
+                surface: DrawTarget::from_backing(
+                    metrics.width as i32,
+                    metrics.height as i32,
+                    data,
+                ),
+            }
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+}
+
+/// Lays out a single line of text; measuring and drawing both go through
+/// here so that they always agree.
+///
+/// The text is appended in runs, each using the first font in `fonts` that
+/// has a glyph for every character in the run.
+fn layout_text(fonts: &[&Font], content: &str, x: usize, y: usize, px: usize) -> Layout {
+    let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+    layout.reset(&LayoutSettings {
+        x: x as f32,
+        y: y as f32,
+        ..LayoutSettings::default()
+    });
+    for (font_index, run) in font_runs(fonts, content) {
+        layout.append(fonts, &TextStyle::new(run, px as f32, font_index));
+    }
+    layout
+}
+
+/// Splits `content` into runs of characters sharing the first font that has
+/// them. Whitespace stays in the current run, and characters no font has are
+/// left to the primary font.
+fn font_runs<'c>(fonts: &[&Font], content: &'c str) -> Vec<(usize, &'c str)> {
+    let mut runs = vec![];
+    let mut run_start = 0;
+    let mut run_font = 0;
+    for (i, c) in content.char_indices() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let font_index = fonts
             .iter()
-            .map(|&cov| rgb_to_u32(0, 0, 0, cov as usize))
-            .collect();
-
-        rc.draw_target.draw_image_at(
-            glyph.x,
-            glyph.y,
-            &raqote::Image {
-                width: metrics.width as i32,
-                height: metrics.height as i32,
-                data: &image_data,
-            },
-            &raqote::DrawOptions::new(),
-        );
+            .position(|f| f.lookup_glyph_index(c) != 0)
+            .unwrap_or(0);
+        if font_index != run_font {
+            if i > run_start {
+                runs.push((run_font, &content[run_start..i]));
+            }
+            run_start = i;
+            run_font = font_index;
+        }
     }
+    if run_start < content.len() {
+        runs.push((run_font, &content[run_start..]));
+    }
+    runs
+}
+
+/// The premultiplied pixel for a glyph's coverage, so that drawing it blends
+/// the text colour over whatever is already painted.
+pub fn glyph_pixel(colour: Colour, coverage: u8) -> u32 {
+    let alpha = colour.a as usize * coverage as usize / 255;
+    let premultiply = |c: u8| c as usize * alpha / 255;
+    rgb_to_u32(
+        premultiply(colour.r),
+        premultiply(colour.g),
+        premultiply(colour.b),
+        alpha,
+    )
 }
 
 /// Packs RGBA components into a `u32` pixel value.
@@ -121,13 +253,40 @@ mod tests {
     #[test]
     fn test_rgb_to_u32_clamps_above_255() {
         // Values > 255 are clamped to 255
-        assert_eq!(rgb_to_u32(255, 255, 255, 255), rgb_to_u32(300, 400, 500, 600));
+        assert_eq!(
+            rgb_to_u32(255, 255, 255, 255),
+            rgb_to_u32(300, 400, 500, 600)
+        );
+    }
+
+    #[test]
+    fn test_glyph_pixel_black_is_coverage_alpha() {
+        assert_eq!(
+            rgb_to_u32(0, 0, 0, 128),
+            glyph_pixel(Colour::rgb(0, 0, 0), 128)
+        );
+    }
+
+    #[test]
+    fn test_glyph_pixel_is_premultiplied() {
+        assert_eq!(
+            rgb_to_u32(255, 127, 0, 255),
+            glyph_pixel(Colour::rgb(255, 127, 0), 255)
+        );
+        assert_eq!(
+            rgb_to_u32(127, 63, 0, 127),
+            glyph_pixel(Colour::rgb(255, 127, 0), 127)
+        );
+        assert_eq!(
+            rgb_to_u32(63, 31, 0, 63),
+            glyph_pixel(Colour::rgba(255, 127, 0, 127), 127)
+        );
     }
 
     #[test]
     fn test_measure_string_single_char() {
         let theme = Theme::default();
-        let size = measure_string(&theme, "A", 20);
+        let size = measure_string(&theme, FontRole::Message, "A", 20);
         assert_eq!(0, size.x);
         assert!(size.y > 0, "y should be > 0 (baseline offset)");
         assert!(size.w > 0, "width should be > 0");
@@ -137,24 +296,111 @@ mod tests {
     #[test]
     fn test_measure_string_two_chars_wider() {
         let theme = Theme::default();
-        let single = measure_string(&theme, "A", 20);
-        let double = measure_string(&theme, "AA", 20);
+        let single = measure_string(&theme, FontRole::Message, "A", 20);
+        let double = measure_string(&theme, FontRole::Message, "AA", 20);
         assert!(double.w > single.w, "two chars should be wider than one");
     }
 
     #[test]
     fn test_measure_string_same_height_for_same_font_size() {
         let theme = Theme::default();
-        let a = measure_string(&theme, "A", 20);
-        let b = measure_string(&theme, "B", 20);
+        let a = measure_string(&theme, FontRole::Message, "A", 20);
+        let b = measure_string(&theme, FontRole::Message, "B", 20);
         assert_eq!(a.h, b.h, "same font size should yield same glyph height");
     }
 
+    #[test]
+    fn test_measure_string_uses_role_font() {
+        let theme = Theme::default();
+        let title = measure_string(&theme, FontRole::Title, "Sequence", 30);
+        let participant = measure_string(&theme, FontRole::Participant, "Sequence", 30);
+        assert_ne!(title.w, participant.w);
+    }
+
+    #[test]
+    fn test_measure_string_matches_drawn_extent() {
+        let theme = Theme::default();
+        let rect = measure_string(&theme, FontRole::Message, "Request", 16);
+        let layout = layout_text(&theme.font_chain(FontRole::Message), "Request", 10, 20, 16);
+        let right = layout
+            .glyphs()
+            .iter()
+            .map(|g| g.x as usize + g.width)
+            .max()
+            .unwrap();
+        assert_eq!(10 + rect.w, right);
+    }
+
+    #[test]
+    fn test_font_runs_use_first_font_with_glyph() {
+        // Open Sans has no rupee sign but Roboto does
+        let open_sans = Theme::default().message_font;
+        let roboto = Theme::default().participant_font;
+        let fonts = [&open_sans, &roboto];
+        assert_eq!(vec![(0, "Pay 5 ")], font_runs(&fonts, "Pay 5 "));
+        assert_eq!(
+            vec![(0, "Pay "), (1, "₹"), (0, "5 now")],
+            font_runs(&fonts, "Pay ₹5 now")
+        );
+        // nothing has it, so it stays with the primary font
+        assert_eq!(vec![(0, "日本")], font_runs(&fonts, "日本"));
+    }
+
+    #[test]
+    fn test_measure_string_falls_back_for_missing_glyphs() {
+        let theme = Theme::default();
+        let fonts = [&theme.message_font, &theme.participant_font];
+        let layout = layout_text(&fonts, "a₹", 0, 0, 20);
+        let font_indices = layout
+            .glyphs()
+            .iter()
+            .map(|g| g.font_index)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1], font_indices);
+
+        // the fallback glyph is measured, not the primary font's missing glyph box
+        let fallback = measure_string(&theme, FontRole::Participant, "₹", 20);
+        let glyph = layout.glyphs()[1];
+        assert_eq!(fallback.w, glyph.width + fallback.x);
+    }
+
+    #[test]
+    fn test_glyph_cache_reuses_images() {
+        let theme = Theme::default();
+        let fonts = theme.font_chain(FontRole::Message);
+        let layout = layout_text(&fonts, "abab", 0, 0, 16);
+        let black = Colour::rgb(0, 0, 0);
+
+        let mut cache = GlyphCache::default();
+        for glyph in layout.glyphs() {
+            cache.get(fonts[glyph.font_index], glyph, 16, black);
+        }
+        assert_eq!(2, cache.len());
+
+        // a new size or colour is a separate image
+        let glyph = &layout.glyphs()[0];
+        cache.get(fonts[0], glyph, 20, black);
+        cache.get(fonts[0], glyph, 16, Colour::rgb(255, 0, 0));
+        assert_eq!(4, cache.len());
+
+        let (metrics, coverage) = fonts[0].rasterize(glyph.parent, 16.0);
+        let image = cache.get(fonts[0], glyph, 16, black);
+        assert_eq!(
+            (metrics.width as i32, metrics.height as i32),
+            (image.surface.width(), image.surface.height())
+        );
+        let expected = coverage
+            .iter()
+            .map(|&cov| glyph_pixel(black, cov))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, image.surface.get_data());
+    }
+
     #[test]
     fn test_measure_string_larger_px_gives_larger_height() {
         let theme = Theme::default();
-        let small = measure_string(&theme, "A", 20);
-        let large = measure_string(&theme, "A", 40);
+        let small = measure_string(&theme, FontRole::Message, "A", 20);
+        let large = measure_string(&theme, FontRole::Message, "A", 40);
         assert!(large.h > small.h, "larger px should produce taller glyphs");
     }
 }
@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::theme::{Colour, Theme};
+
+// == Theme File ==========================================
+/// A user supplied theme, e.g.
+///
+/// ```toml
+/// title_font = "fonts/Brand-Bold.otf"
+/// fallback_fonts = ["fonts/NotoSansJP-Regular.otf", "fonts/NotoSansSymbols2-Regular.ttf"]
+/// partic_font_px = 24
+/// partic_h_gap = 40
+///
+/// [colours]
+/// background = "#f8f8f2"
+/// participant_border = "#004488cc"
+/// ```
+///
+/// Every key is optional and falls back to the default theme. Font paths are
+/// relative to the theme file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    title_font: Option<PathBuf>,
+    participant_font: Option<PathBuf>,
+    message_font: Option<PathBuf>,
+    note_font: Option<PathBuf>,
+    fallback_fonts: Option<Vec<PathBuf>>,
+    title_font_family: Option<String>,
+    partic_font_family: Option<String>,
+    message_font_family: Option<String>,
+    note_font_family: Option<String>,
+    title_font_weight: Option<u16>,
+    partic_font_weight: Option<u16>,
+    message_font_weight: Option<u16>,
+    note_font_weight: Option<u16>,
+    title_font_px: Option<usize>,
+    partic_font_px: Option<usize>,
+    message_font_px: Option<usize>,
+    note_font_px: Option<usize>,
+    document_border_width: Option<usize>,
+    partic_padding: Option<usize>,
+    partic_h_gap: Option<usize>,
+    interaction_row_h: Option<usize>,
+    arrow_head_size: Option<usize>,
+    self_ref_w: Option<usize>,
+    activation_bars: Option<bool>,
+    activation_bar_w: Option<usize>,
+    footbox: Option<bool>,
+    #[serde(default)]
+    colours: ColoursFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColoursFile {
+    background: Option<String>,
+    participant_fill: Option<String>,
+    participant_border: Option<String>,
+    lifeline: Option<String>,
+    activation_fill: Option<String>,
+    note_fill: Option<String>,
+    note_border: Option<String>,
+    fragment_border: Option<String>,
+    arrow: Option<String>,
+    message_text: Option<String>,
+    title_text: Option<String>,
+}
+
+impl Theme {
+    /// Loads a TOML theme file, see `ThemeFile` for the keys.
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Theme> {
+        let path = path.as_ref();
+        let invalid = |reason: String| Error::Theme(format!("{}: {}", path.display(), reason));
+
+        let content = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let file: ThemeFile = toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let load_font = |font: &Path| {
+            let font_path = base_dir.join(font);
+            std::fs::read(&font_path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| Theme::load_font(&data).map_err(|e| e.to_string()))
+                .map_err(|e| invalid(format!("font `{}`: {}", font_path.display(), e)))
+        };
+
+        let mut theme = Theme::default();
+        let font_fields = [
+            (&file.title_font, &mut theme.title_font),
+            (&file.participant_font, &mut theme.participant_font),
+            (&file.message_font, &mut theme.message_font),
+            (&file.note_font, &mut theme.note_font),
+        ];
+        for (path, field) in font_fields {
+            if let Some(path) = path {
+                *field = load_font(path)?;
+            }
+        }
+        if let Some(paths) = &file.fallback_fonts {
+            theme.fallback_fonts = paths.iter().map(|p| load_font(p)).collect::<Result<_>>()?;
+        }
+        apply(&mut theme.title_font_family, file.title_font_family);
+        apply(&mut theme.partic_font_family, file.partic_font_family);
+        apply(&mut theme.message_font_family, file.message_font_family);
+        apply(&mut theme.note_font_family, file.note_font_family);
+        apply(&mut theme.title_font_weight, file.title_font_weight);
+        apply(&mut theme.partic_font_weight, file.partic_font_weight);
+        apply(&mut theme.message_font_weight, file.message_font_weight);
+        apply(&mut theme.note_font_weight, file.note_font_weight);
+        apply(&mut theme.title_font_px, file.title_font_px);
+        apply(&mut theme.partic_font_px, file.partic_font_px);
+        apply(&mut theme.message_font_px, file.message_font_px);
+        apply(&mut theme.note_font_px, file.note_font_px);
+        apply(&mut theme.document_border_width, file.document_border_width);
+        apply(&mut theme.partic_padding, file.partic_padding);
+        apply(&mut theme.partic_h_gap, file.partic_h_gap);
+        apply(&mut theme.interaction_row_h, file.interaction_row_h);
+        apply(&mut theme.arrow_head_size, file.arrow_head_size);
+        apply(&mut theme.self_ref_w, file.self_ref_w);
+        apply(&mut theme.activation_bars, file.activation_bars);
+        apply(&mut theme.activation_bar_w, file.activation_bar_w);
+        apply(&mut theme.footbox, file.footbox);
+
+        let colours = file.colours;
+        let colour_fields = [
+            ("background", colours.background, &mut theme.background),
+            (
+                "participant_fill",
+                colours.participant_fill,
+                &mut theme.participant_fill,
+            ),
+            (
+                "participant_border",
+                colours.participant_border,
+                &mut theme.participant_border,
+            ),
+            ("lifeline", colours.lifeline, &mut theme.lifeline),
+            (
+                "activation_fill",
+                colours.activation_fill,
+                &mut theme.activation_fill,
+            ),
+            ("note_fill", colours.note_fill, &mut theme.note_fill),
+            ("note_border", colours.note_border, &mut theme.note_border),
+            (
+                "fragment_border",
+                colours.fragment_border,
+                &mut theme.fragment_border,
+            ),
+            ("arrow", colours.arrow, &mut theme.arrow),
+            (
+                "message_text",
+                colours.message_text,
+                &mut theme.message_text,
+            ),
+            ("title_text", colours.title_text, &mut theme.title_text),
+        ];
+        for (key, value, field) in colour_fields {
+            if let Some(value) = value {
+                *field = Colour::from_hex(&value).ok_or_else(|| {
+                    invalid(format!(
+                        "colours.{}: expected `#rrggbb` or `#rrggbbaa`, found `{}`",
+                        key, value
+                    ))
+                })?;
+            }
+        }
+
+        // fonts, rows and shapes can't be drawn at no size, and layout divides
+        // by the row height
+        for (key, size) in [
+            ("title_font_px", theme.title_font_px),
+            ("partic_font_px", theme.partic_font_px),
+            ("message_font_px", theme.message_font_px),
+            ("note_font_px", theme.note_font_px),
+            ("interaction_row_h", theme.interaction_row_h),
+            ("arrow_head_size", theme.arrow_head_size),
+            ("self_ref_w", theme.self_ref_w),
+            ("activation_bar_w", theme.activation_bar_w),
+        ] {
+            if size == 0 {
+                return Err(invalid(format!("{} must be greater than 0", key)));
+            }
+        }
+
+        for (key, weight) in [
+            ("title_font_weight", theme.title_font_weight),
+            ("partic_font_weight", theme.partic_font_weight),
+            ("message_font_weight", theme.message_font_weight),
+            ("note_font_weight", theme.note_font_weight),
+        ] {
+            if !(1..=1000).contains(&weight) {
+                return Err(invalid(format!("{} must be between 1 and 1000", key)));
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+fn apply<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_theme(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sequencer-theme-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_file_overrides_and_falls_back() {
+        let path = write_theme(
+            "brand.toml",
+            "partic_h_gap = 42\nfootbox = true\n\n[colours]\nbackground = \"#102030\"\narrow = \"#ff000080\"\n",
+        );
+        let theme = Theme::load_file(&path).unwrap();
+        let default = Theme::default();
+        assert_eq!(42, theme.partic_h_gap);
+        assert!(theme.footbox);
+        assert_eq!(Colour::rgb(16, 32, 48), theme.background);
+        assert_eq!(Colour::rgba(255, 0, 0, 128), theme.arrow);
+        assert_eq!(default.partic_padding, theme.partic_padding);
+        assert_eq!(default.lifeline, theme.lifeline);
+    }
+
+    #[test]
+    fn test_load_file_rejects_unknown_keys() {
+        let path = write_theme("unknown.toml", "partic_gap = 4\n");
+        let error = Theme::load_file(&path).unwrap_err().to_string();
+        assert!(error.contains("unknown field `partic_gap`"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_file_rejects_bad_colour() {
+        let path = write_theme("colour.toml", "[colours]\nlifeline = \"grey\"\n");
+        let error = Theme::load_file(&path).unwrap_err().to_string();
+        assert!(error.contains("colours.lifeline"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_file_rejects_unreadable_font() {
+        let path = write_theme("font.toml", "message_font = \"missing.ttf\"\n");
+        let error = Theme::load_file(&path).unwrap_err().to_string();
+        assert!(error.contains("missing.ttf"), "{}", error);
+
+        let path = write_theme("not-a-font.toml", "message_font = \"font.toml\"\n");
+        assert!(matches!(Theme::load_file(&path), Err(Error::Theme(_))));
+
+        let path = write_theme("fallback.toml", "fallback_fonts = [\"missing.otf\"]\n");
+        let error = Theme::load_file(&path).unwrap_err().to_string();
+        assert!(error.contains("missing.otf"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_file_rejects_zero_sizes() {
+        for key in [
+            "title_font_px",
+            "partic_font_px",
+            "message_font_px",
+            "note_font_px",
+            "interaction_row_h",
+            "arrow_head_size",
+            "self_ref_w",
+            "activation_bar_w",
+        ] {
+            let path = write_theme(&format!("zero-{}.toml", key), &format!("{} = 0\n", key));
+            let error = Theme::load_file(&path).unwrap_err();
+            assert!(matches!(error, Error::Theme(_)), "{}", key);
+            assert!(error.to_string().contains(key), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_load_file_font_families_and_weights() {
+        let path = write_theme(
+            "families.toml",
+            "title_font_family = \"Brand, serif\"\ntitle_font_weight = 700\n",
+        );
+        let theme = Theme::load_file(&path).unwrap();
+        assert_eq!("Brand, serif", theme.title_font_family);
+        assert_eq!(700, theme.title_font_weight);
+        assert_eq!(Theme::default().note_font_family, theme.note_font_family);
+
+        let path = write_theme("weight.toml", "message_font_weight = 1200\n");
+        let error = Theme::load_file(&path).unwrap_err().to_string();
+        assert!(error.contains("message_font_weight"), "{}", error);
+    }
+}
@@ -0,0 +1,395 @@
+use fontdue::{Font, FontSettings};
+
+use crate::error::{Error, Result};
+
+mod file;
+
+// == Colour ==============================================
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Colour {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Colour {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Colour { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Colour { r, g, b, a }
+    }
+
+    /// Parses `#rrggbb` or `#rrggbbaa`.
+    pub fn from_hex(hex: &str) -> Option<Colour> {
+        let hex = hex.strip_prefix('#')?;
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Colour {
+            r: component(0)?,
+            g: component(2)?,
+            b: component(4)?,
+            a: if hex.len() == 8 { component(6)? } else { 255 },
+        })
+    }
+}
+
+// == Font Role ===========================================
+/// Which of the theme's fonts a piece of text is drawn with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FontRole {
+    Title,
+    Participant,
+    Message,
+    Note,
+}
+
+// == Theme ===============================================
+/// Names of the built-in themes, as accepted by `:theme` and `--theme`.
+pub const THEME_NAMES: [&str; 4] = ["Default", "Dark", "Monochrome", "High-contrast"];
+
+/// Prefix of a `:theme` value naming a theme file rather than a built-in theme.
+pub const THEME_FILE_PREFIX: &str = "file:";
+
+const OPEN_SANS_REGULAR: &[u8] = include_bytes!("../../assets/OpenSans-Regular.ttf");
+const ROBOTO_BLACK: &[u8] = include_bytes!("../../assets/Roboto-Black.ttf");
+const ROBOTO_THIN: &[u8] = include_bytes!("../../assets/Roboto-Thin.ttf");
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub title_font: Font,
+    pub participant_font: Font,
+    pub message_font: Font,
+    pub note_font: Font,
+    /// Tried in order for characters missing from a role's own font.
+    pub fallback_fonts: Vec<Font>,
+    /// CSS font families and weights naming each role's font in SVG output.
+    pub title_font_family: String,
+    pub partic_font_family: String,
+    pub message_font_family: String,
+    pub note_font_family: String,
+    pub title_font_weight: u16,
+    pub partic_font_weight: u16,
+    pub message_font_weight: u16,
+    pub note_font_weight: u16,
+    pub background: Colour,
+    pub participant_fill: Colour,
+    pub participant_border: Colour,
+    pub lifeline: Colour,
+    pub activation_fill: Colour,
+    pub note_fill: Colour,
+    pub note_border: Colour,
+    pub fragment_border: Colour,
+    pub arrow: Colour,
+    /// Participant names and message labels.
+    pub message_text: Colour,
+    /// The title, author and date header.
+    pub title_text: Colour,
+    pub title_font_px: usize,
+    pub partic_font_px: usize,
+    pub message_font_px: usize,
+    pub note_font_px: usize,
+    pub document_border_width: usize,
+    pub partic_padding: usize,
+    pub partic_h_gap: usize,
+    pub interaction_row_h: usize,
+    pub arrow_head_size: usize,
+    pub self_ref_w: usize,
+    pub activation_bars: bool,
+    pub activation_bar_w: usize,
+    /// Repeats the participant heads below the lifelines.
+    pub footbox: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            title_font: Theme::load_bundled_font(ROBOTO_BLACK),
+            participant_font: Theme::load_bundled_font(ROBOTO_THIN),
+            message_font: Theme::load_bundled_font(OPEN_SANS_REGULAR),
+            note_font: Theme::load_bundled_font(OPEN_SANS_REGULAR),
+            fallback_fonts: vec![
+                Theme::load_bundled_font(OPEN_SANS_REGULAR),
+                Theme::load_bundled_font(ROBOTO_THIN),
+            ],
+            title_font_family: "Roboto, sans-serif".to_string(),
+            partic_font_family: "Roboto, sans-serif".to_string(),
+            message_font_family: "'Open Sans', sans-serif".to_string(),
+            note_font_family: "'Open Sans', sans-serif".to_string(),
+            title_font_weight: 900,
+            partic_font_weight: 100,
+            message_font_weight: 400,
+            note_font_weight: 400,
+            background: Colour::rgb(255, 255, 255),
+            participant_fill: Colour::rgb(255, 255, 255),
+            participant_border: Colour::rgba(255, 20, 20, 225),
+            lifeline: Colour::rgb(80, 80, 80),
+            activation_fill: Colour::rgb(255, 255, 255),
+            note_fill: Colour::rgb(255, 250, 205),
+            note_border: Colour::rgb(170, 150, 60),
+            fragment_border: Colour::rgb(80, 80, 80),
+            arrow: Colour::rgb(0, 0, 0),
+            message_text: Colour::rgb(0, 0, 0),
+            title_text: Colour::rgb(0, 0, 0),
+            title_font_px: 30,
+            partic_font_px: 30,
+            message_font_px: 16,
+            note_font_px: 14,
+            document_border_width: 10,
+            partic_padding: 5,
+            partic_h_gap: 20,
+            interaction_row_h: 40,
+            arrow_head_size: 8,
+            self_ref_w: 30,
+            activation_bars: true,
+            activation_bar_w: 10,
+            footbox: false,
+        }
+    }
+}
+
+impl Theme {
+    /// Looks up a built-in theme by name, ignoring case.
+    pub fn named(name: &str) -> Option<Theme> {
+        match name.to_lowercase().as_str() {
+            "default" => Some(Theme::default()),
+            "dark" => Some(Theme::dark()),
+            "monochrome" => Some(Theme::monochrome()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Resolves a `:theme` value, either a built-in theme name or
+    /// `file:` followed by the path of a theme file. Unknown names fall back
+    /// to the default theme.
+    pub fn resolve(value: &str) -> Result<Theme> {
+        match value.strip_prefix(THEME_FILE_PREFIX) {
+            Some(path) => Theme::load_file(path.trim()),
+            None => Ok(Theme::named(value).unwrap_or_default()),
+        }
+    }
+
+    pub fn is_built_in(name: &str) -> bool {
+        THEME_NAMES.iter().any(|n| n.eq_ignore_ascii_case(name))
+    }
+
+    /// Light strokes on a dark background.
+    pub fn dark() -> Self {
+        let open_sans = Theme::load_bundled_font(OPEN_SANS_REGULAR);
+        Theme {
+            title_font: open_sans.clone(),
+            participant_font: open_sans.clone(),
+            message_font: open_sans.clone(),
+            note_font: open_sans,
+            title_font_family: "'Open Sans', sans-serif".to_string(),
+            partic_font_family: "'Open Sans', sans-serif".to_string(),
+            message_font_family: "'Open Sans', sans-serif".to_string(),
+            note_font_family: "'Open Sans', sans-serif".to_string(),
+            title_font_weight: 400,
+            partic_font_weight: 400,
+            message_font_weight: 400,
+            note_font_weight: 400,
+            background: Colour::rgb(30, 30, 36),
+            participant_fill: Colour::rgb(44, 48, 62),
+            participant_border: Colour::rgb(120, 170, 255),
+            lifeline: Colour::rgb(150, 150, 160),
+            activation_fill: Colour::rgb(48, 48, 56),
+            note_fill: Colour::rgb(62, 60, 44),
+            note_border: Colour::rgb(200, 190, 120),
+            fragment_border: Colour::rgb(150, 150, 160),
+            arrow: Colour::rgb(220, 220, 220),
+            message_text: Colour::rgb(225, 225, 230),
+            title_text: Colour::rgb(255, 255, 255),
+            partic_font_px: 26,
+            partic_padding: 6,
+            partic_h_gap: 30,
+            ..Theme::default()
+        }
+    }
+
+    /// Black and grey only, suitable for printing.
+    pub fn monochrome() -> Self {
+        let open_sans = Theme::load_bundled_font(OPEN_SANS_REGULAR);
+        Theme {
+            title_font: Theme::load_bundled_font(ROBOTO_BLACK),
+            participant_font: open_sans.clone(),
+            message_font: open_sans.clone(),
+            note_font: open_sans,
+            title_font_family: "Roboto, sans-serif".to_string(),
+            partic_font_family: "'Open Sans', sans-serif".to_string(),
+            message_font_family: "'Open Sans', sans-serif".to_string(),
+            note_font_family: "'Open Sans', sans-serif".to_string(),
+            title_font_weight: 900,
+            partic_font_weight: 400,
+            message_font_weight: 400,
+            note_font_weight: 400,
+            participant_fill: Colour::rgb(245, 245, 245),
+            participant_border: Colour::rgb(0, 0, 0),
+            lifeline: Colour::rgb(120, 120, 120),
+            activation_fill: Colour::rgb(230, 230, 230),
+            note_fill: Colour::rgb(250, 250, 250),
+            note_border: Colour::rgb(0, 0, 0),
+            fragment_border: Colour::rgb(0, 0, 0),
+            title_text: Colour::rgb(40, 40, 40),
+            title_font_px: 26,
+            partic_font_px: 24,
+            message_font_px: 15,
+            ..Theme::default()
+        }
+    }
+
+    /// Heavy text and larger spacing for readability.
+    pub fn high_contrast() -> Self {
+        let roboto_black = Theme::load_bundled_font(ROBOTO_BLACK);
+        Theme {
+            title_font: roboto_black.clone(),
+            participant_font: roboto_black.clone(),
+            message_font: roboto_black.clone(),
+            note_font: roboto_black,
+            title_font_family: "Roboto, sans-serif".to_string(),
+            partic_font_family: "Roboto, sans-serif".to_string(),
+            message_font_family: "Roboto, sans-serif".to_string(),
+            note_font_family: "Roboto, sans-serif".to_string(),
+            title_font_weight: 900,
+            partic_font_weight: 900,
+            message_font_weight: 900,
+            note_font_weight: 900,
+            participant_border: Colour::rgb(0, 0, 0),
+            lifeline: Colour::rgb(0, 0, 0),
+            activation_fill: Colour::rgb(255, 230, 0),
+            note_fill: Colour::rgb(255, 255, 255),
+            note_border: Colour::rgb(0, 0, 0),
+            fragment_border: Colour::rgb(0, 0, 0),
+            title_font_px: 32,
+            message_font_px: 20,
+            note_font_px: 18,
+            document_border_width: 16,
+            partic_padding: 8,
+            partic_h_gap: 32,
+            interaction_row_h: 50,
+            arrow_head_size: 10,
+            ..Theme::default()
+        }
+    }
+
+    pub fn font(&self, role: FontRole) -> &Font {
+        match role {
+            FontRole::Title => &self.title_font,
+            FontRole::Participant => &self.participant_font,
+            FontRole::Message => &self.message_font,
+            FontRole::Note => &self.note_font,
+        }
+    }
+
+    /// The role's font followed by the fallback fonts.
+    pub fn font_chain(&self, role: FontRole) -> Vec<&Font> {
+        std::iter::once(self.font(role))
+            .chain(self.fallback_fonts.iter())
+            .collect()
+    }
+
+    pub fn font_px(&self, role: FontRole) -> usize {
+        match role {
+            FontRole::Title => self.title_font_px,
+            FontRole::Participant => self.partic_font_px,
+            FontRole::Message => self.message_font_px,
+            FontRole::Note => self.note_font_px,
+        }
+    }
+
+    /// The CSS font family naming the role's font.
+    pub fn font_family(&self, role: FontRole) -> &str {
+        match role {
+            FontRole::Title => &self.title_font_family,
+            FontRole::Participant => &self.partic_font_family,
+            FontRole::Message => &self.message_font_family,
+            FontRole::Note => &self.note_font_family,
+        }
+    }
+
+    /// The CSS font weight of the role's font.
+    pub fn font_weight(&self, role: FontRole) -> u16 {
+        match role {
+            FontRole::Title => self.title_font_weight,
+            FontRole::Participant => self.partic_font_weight,
+            FontRole::Message => self.message_font_weight,
+            FontRole::Note => self.note_font_weight,
+        }
+    }
+
+    /// The colour text in the given role is painted with.
+    pub fn text_colour(&self, role: FontRole) -> Colour {
+        match role {
+            FontRole::Title => self.title_text,
+            FontRole::Participant | FontRole::Message | FontRole::Note => self.message_text,
+        }
+    }
+
+    /// Parses TTF/OTF font data.
+    pub fn load_font(font_data: &[u8]) -> Result<Font> {
+        let settings = FontSettings {
+            collection_index: 0,
+            scale: 18.0,
+            load_substitutions: true,
+        };
+        Font::from_bytes(font_data, settings).map_err(|e| Error::Font(e.to_string()))
+    }
+
+    /// Fonts embedded from `assets/` are known to be valid.
+    fn load_bundled_font(font_data: &[u8]) -> Font {
+        Theme::load_font(font_data).expect("bundled font should be valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_font_rejects_invalid_data() {
+        assert!(matches!(
+            Theme::load_font(b"not a font"),
+            Err(Error::Font(_))
+        ));
+    }
+
+    #[test]
+    fn test_named_themes() {
+        for name in THEME_NAMES {
+            assert!(Theme::is_built_in(name));
+            assert!(Theme::named(name).is_some());
+        }
+        assert_eq!(
+            Theme::dark().background,
+            Theme::named("DARK").unwrap().background
+        );
+        assert!(!Theme::is_built_in("Neon"));
+        assert!(Theme::named("Neon").is_none());
+    }
+
+    #[test]
+    fn test_colour_from_hex() {
+        assert_eq!(Some(Colour::rgb(255, 0, 16)), Colour::from_hex("#ff0010"));
+        assert_eq!(
+            Some(Colour::rgba(0, 0, 0, 128)),
+            Colour::from_hex("#00000080")
+        );
+        assert_eq!(None, Colour::from_hex("ff0010"));
+        assert_eq!(None, Colour::from_hex("#ff00"));
+        assert_eq!(None, Colour::from_hex("#gg0000"));
+    }
+
+    #[test]
+    fn test_resolve_theme_file_errors() {
+        assert!(matches!(
+            Theme::resolve("file: /does/not/exist.toml"),
+            Err(Error::Theme(_))
+        ));
+        assert!(Theme::resolve("Neon").is_ok());
+    }
+}